@@ -0,0 +1,2 @@
+ALTER TABLE rsp_blocks
+    ADD block_hash VARCHAR(66);
\ No newline at end of file
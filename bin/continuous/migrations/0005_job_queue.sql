@@ -0,0 +1,6 @@
+ALTER TABLE rsp_blocks
+    ADD worker_id VARCHAR(255),
+    ADD lease_expires_at TIMESTAMP,
+    ADD retry_count INT NOT NULL DEFAULT 0;
+
+CREATE INDEX IF NOT EXISTS rsp_blocks_status_idx ON rsp_blocks (status, block_number);
\ No newline at end of file
@@ -0,0 +1,3 @@
+ALTER TABLE rsp_blocks
+    ADD error_class VARCHAR(50),
+    ADD next_attempt_at TIMESTAMP;
\ No newline at end of file
@@ -0,0 +1,3 @@
+ALTER TABLE rsp_blocks
+    ADD proving_duration_ms BIGINT,
+    ADD proving_cycles BIGINT;
\ No newline at end of file
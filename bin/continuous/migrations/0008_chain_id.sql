@@ -0,0 +1,13 @@
+ALTER TABLE rsp_blocks
+    ADD chain_id BIGINT NOT NULL DEFAULT 1;
+
+ALTER TABLE rsp_blocks
+    ALTER COLUMN chain_id DROP DEFAULT;
+
+ALTER TABLE rsp_blocks
+    DROP CONSTRAINT rsp_blocks_pkey,
+    ADD PRIMARY KEY (chain_id, block_number);
+
+DROP INDEX IF EXISTS rsp_blocks_status_idx;
+
+CREATE INDEX rsp_blocks_status_idx ON rsp_blocks (chain_id, status, block_number);
\ No newline at end of file
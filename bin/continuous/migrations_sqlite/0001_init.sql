@@ -0,0 +1,16 @@
+CREATE TABLE IF NOT EXISTS rsp_blocks (
+    block_number INTEGER PRIMARY KEY,
+    block_hash TEXT,
+    status TEXT NOT NULL,
+    gas_used INTEGER NOT NULL,
+    tx_count INTEGER NOT NULL,
+    num_cycles INTEGER NOT NULL,
+    sp1_gas INTEGER,
+    start_time DATETIME,
+    end_time DATETIME,
+    worker_id TEXT,
+    lease_expires_at DATETIME,
+    retry_count INTEGER NOT NULL DEFAULT 0
+);
+
+CREATE INDEX IF NOT EXISTS rsp_blocks_status_idx ON rsp_blocks (status, block_number);
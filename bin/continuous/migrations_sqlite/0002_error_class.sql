@@ -0,0 +1,3 @@
+ALTER TABLE rsp_blocks ADD error_class TEXT;
+
+ALTER TABLE rsp_blocks ADD next_attempt_at DATETIME;
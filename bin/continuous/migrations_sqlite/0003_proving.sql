@@ -0,0 +1,3 @@
+ALTER TABLE rsp_blocks ADD proving_duration_ms INTEGER;
+
+ALTER TABLE rsp_blocks ADD proving_cycles INTEGER;
@@ -0,0 +1,38 @@
+-- SQLite can't change the primary key of a table, so the table is rebuilt.
+CREATE TABLE rsp_blocks_new (
+    chain_id INTEGER NOT NULL,
+    block_number INTEGER NOT NULL,
+    block_hash TEXT,
+    status TEXT NOT NULL,
+    gas_used INTEGER NOT NULL,
+    tx_count INTEGER NOT NULL,
+    num_cycles INTEGER NOT NULL,
+    sp1_gas INTEGER,
+    start_time DATETIME,
+    end_time DATETIME,
+    worker_id TEXT,
+    lease_expires_at DATETIME,
+    retry_count INTEGER NOT NULL DEFAULT 0,
+    error_class TEXT,
+    next_attempt_at DATETIME,
+    proving_duration_ms INTEGER,
+    proving_cycles INTEGER,
+    PRIMARY KEY (chain_id, block_number)
+);
+
+INSERT INTO rsp_blocks_new (
+    chain_id, block_number, block_hash, status, gas_used, tx_count, num_cycles, sp1_gas,
+    start_time, end_time, worker_id, lease_expires_at, retry_count, error_class, next_attempt_at,
+    proving_duration_ms, proving_cycles
+)
+SELECT
+    1, block_number, block_hash, status, gas_used, tx_count, num_cycles, sp1_gas,
+    start_time, end_time, worker_id, lease_expires_at, retry_count, error_class, next_attempt_at,
+    proving_duration_ms, proving_cycles
+FROM rsp_blocks;
+
+DROP TABLE rsp_blocks;
+
+ALTER TABLE rsp_blocks_new RENAME TO rsp_blocks;
+
+CREATE INDEX rsp_blocks_status_idx ON rsp_blocks (chain_id, status, block_number);
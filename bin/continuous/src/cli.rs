@@ -1,6 +1,13 @@
+use std::path::PathBuf;
+
+use alloy_chains::Chain;
 use clap::Parser;
+use rsp_host_executor::{genesis_from_path, parse_proof_mode, Config};
+use sp1_sdk::SP1ProofMode;
 use url::Url;
 
+use crate::proving::ProvingPolicy;
+
 /// The arguments for the cli.
 #[derive(Debug, Clone, Parser)]
 pub struct Args {
@@ -12,19 +19,158 @@ pub struct Args {
     #[clap(long, env)]
     pub ws_rpc_url: Url,
 
+    /// The chain ID. Defaults to the chain ID of the HTTP RPC.
+    #[clap(long, env)]
+    pub chain_id: Option<u64>,
+
+    /// The path to the genesis json file of the chain, required for the chains not supported
+    /// natively.
+    #[clap(long, env)]
+    pub genesis_path: Option<PathBuf>,
+
     /// The database connection string.
     #[clap(long, env)]
     pub database_url: String,
 
-    /// The maximum number of concurrent executions.
+    /// The maximum number of concurrent executions, i.e. the number of workers pulling blocks
+    /// from the queue.
     #[clap(long, env)]
     pub max_concurrent_executions: usize,
 
-    /// Retry count on failed execution.
+    /// The identifier of this instance in the queue. Defaults to `<hostname>-<pid>`.
+    #[clap(long, env)]
+    pub worker_id: Option<String>,
+
+    /// How long a worker owns a block before other workers can claim it, in seconds. The lease is
+    /// renewed while the block is being executed.
+    #[clap(long, env, default_value_t = 300)]
+    pub lease_duration: u64,
+
+    /// The first block of a range of historical blocks to enqueue at startup.
+    #[clap(long, env, requires = "enqueue_to")]
+    pub enqueue_from: Option<u64>,
+
+    /// The last block of a range of historical blocks to enqueue at startup.
+    #[clap(long, env, requires = "enqueue_from")]
+    pub enqueue_to: Option<u64>,
+
+    /// The maximum number of blocks before the chain head to backfill.
+    #[clap(long, env, default_value_t = 1000)]
+    pub max_backfill_blocks: u64,
+
+    /// The block to start backfilling from, instead of the lowest block not executed.
+    #[clap(long, env)]
+    pub backfill_from: Option<u64>,
+
+    /// Retry count on failed execution. Failed blocks are put back in the queue until this count
+    /// is reached, with an exponential backoff for RPC errors. Deterministic failures, like a
+    /// state root mismatch, are not retried.
     #[clap(long, env, default_value_t = 3)]
     pub execution_retries: usize,
 
+    /// Prove every Nth block, in addition to executing it.
+    #[clap(
+        long,
+        env,
+        value_parser = clap::value_parser!(u64).range(1..),
+        conflicts_with = "prove_sample_rate"
+    )]
+    pub prove_every: Option<u64>,
+
+    /// Prove a fraction of the blocks, between 0 and 1, in addition to executing them.
+    #[clap(long, env, value_parser = parse_sample_rate)]
+    pub prove_sample_rate: Option<f64>,
+
+    /// The maximum number of concurrent proofs, counted within the concurrent executions.
+    #[clap(long, env, default_value_t = 1)]
+    pub max_concurrent_proofs: usize,
+
+    /// The proof mode: core, compressed, groth16 or plonk.
+    #[clap(long, env, value_parser = parse_proof_mode, default_value = "compressed")]
+    pub proof_mode: SP1ProofMode,
+
+    /// Whether to verify the proofs before saving them.
+    #[clap(long, env)]
+    pub verify_proof: bool,
+
+    /// Skip the pruning of the trie nodes that the client does not need from the client input.
+    #[clap(long, env)]
+    pub no_minimize_witness: bool,
+
+    /// The directory where the proofs and their verification key are written, in a subdirectory
+    /// named after the chain ID.
+    #[clap(long, env)]
+    pub proof_dir: Option<PathBuf>,
+
+    /// The endpoint of a remote proving service generating the proofs, instead of the local
+    /// prover.
+    #[clap(long, env)]
+    pub remote_prover_url: Option<Url>,
+
+    /// How long a remote proving job is waited for before giving up on it, in seconds.
+    #[clap(long, env, default_value_t = 3600)]
+    pub remote_prover_timeout: u64,
+
     /// PagerDuty integration key.
     #[clap(long, env)]
     pub pager_duty_integration_key: Option<String>,
+
+    /// Overrides the PagerDuty events endpoint.
+    #[clap(long, env)]
+    pub pager_duty_endpoint: Option<Url>,
+
+    /// Slack or Discord compatible webhook URL, used when no PagerDuty key is set.
+    #[clap(long, env)]
+    pub alert_webhook_url: Option<Url>,
+
+    /// Log the alerts when no alerting backend is configured.
+    #[clap(long, env)]
+    pub log_alerts: bool,
+}
+
+impl Args {
+    /// Returns the worker ID, or a default one built from the hostname and the process ID.
+    pub fn worker_id(&self) -> String {
+        self.worker_id.clone().unwrap_or_else(|| {
+            let hostname = std::env::var("HOSTNAME").unwrap_or_else(|_| String::from("continuous"));
+            format!("{hostname}-{}", std::process::id())
+        })
+    }
+
+    /// Returns the execution config of the chain.
+    pub fn as_config(&self, chain_id: u64) -> eyre::Result<Config> {
+        let genesis = if let Some(genesis_path) = &self.genesis_path {
+            genesis_from_path(genesis_path)?
+        } else {
+            chain_id.try_into()?
+        };
+
+        Ok(Config {
+            chain: Chain::from_id(chain_id),
+            genesis,
+            rpc_url: Some(self.http_rpc_url.clone()),
+            minimize_witness: !self.no_minimize_witness,
+            ..Config::mainnet()
+        })
+    }
+
+    /// Returns the policy selecting the blocks to prove, if proving is enabled.
+    pub fn proving_policy(&self) -> Option<ProvingPolicy> {
+        match (self.prove_every, self.prove_sample_rate) {
+            (Some(interval), _) => Some(ProvingPolicy::Every(interval)),
+            (None, Some(rate)) => Some(ProvingPolicy::SampleRate(rate)),
+            (None, None) => None,
+        }
+    }
+}
+
+/// Parses a sample rate, between 0 and 1.
+fn parse_sample_rate(s: &str) -> Result<f64, String> {
+    let rate = s.parse::<f64>().map_err(|err| err.to_string())?;
+
+    if !(0.0..=1.0).contains(&rate) {
+        return Err(format!("{rate} is not between 0 and 1"));
+    }
+
+    Ok(rate)
 }
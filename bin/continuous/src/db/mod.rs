@@ -0,0 +1,506 @@
+//! Persistence of the block statuses, backed either by Postgres or by SQLite.
+
+use std::{fmt::Display, future::Future, path::PathBuf, str::FromStr, sync::Arc, time::Duration};
+
+use alloy_consensus::{Block, BlockHeader};
+use alloy_primitives::B256;
+use eyre::bail;
+use reth_primitives_traits::NodePrimitives;
+use rsp_host_executor::ExecutionHooks;
+use sp1_sdk::{ExecutionReport, SP1ProofWithPublicValues, SP1VerifyingKey};
+use sqlx::types::chrono::{self, NaiveDateTime};
+
+use crate::{proving::save_proof, retry::ErrorClass};
+
+pub use postgres::PostgresStore;
+pub use sqlite::SqliteStore;
+
+mod postgres;
+
+mod sqlite;
+
+/// The `rsp_blocks` work queue, and the execution results.
+pub trait BlockStore: Send + Sync {
+    /// Adds a block to the queue, if it's not already there.
+    ///
+    /// If the block hash is provided and the block was already executed with another hash, the
+    /// block is marked as reorged so it's executed again.
+    fn enqueue_block(
+        &self,
+        block_number: u64,
+        block_hash: Option<B256>,
+    ) -> impl Future<Output = eyre::Result<()>> + Send;
+
+    /// Adds all the blocks in the `[from, to]` range that are not already there to the queue.
+    ///
+    /// Returns the number of blocks added.
+    fn enqueue_blocks(&self, from: u64, to: u64) -> impl Future<Output = eyre::Result<u64>> + Send;
+
+    /// Claims the highest block available in the queue for the given worker, or the lowest one
+    /// if `oldest_first` is set.
+    ///
+    /// Available blocks are the queued and reorged ones whose retry delay elapsed, and the
+    /// running ones whose lease expired, meaning the worker that claimed them died.
+    fn claim_block(
+        &self,
+        worker_id: &str,
+        lease_duration: Duration,
+        oldest_first: bool,
+    ) -> impl Future<Output = eyre::Result<Option<ClaimedBlock>>> + Send;
+
+    /// Extends the lease of a block claimed by the given worker.
+    ///
+    /// Returns false if the worker doesn't own the block anymore.
+    fn renew_lease(
+        &self,
+        block_number: u64,
+        worker_id: &str,
+        lease_duration: Duration,
+    ) -> impl Future<Output = eyre::Result<bool>> + Send;
+
+    /// Stores the execution results of a block claimed by the given worker, and updates its
+    /// status.
+    ///
+    /// Blocks being proven stay running until the proof is generated.
+    ///
+    /// Returns false if the worker doesn't own the block anymore, in which case nothing is
+    /// stored.
+    fn update_block_status(
+        &self,
+        block_number: u64,
+        worker_id: &str,
+        block_hash: B256,
+        gas_used: u64,
+        tx_count: usize,
+        num_cycles: u64,
+        sp1_gas: u64,
+        status: ProvableBlockStatus,
+    ) -> impl Future<Output = eyre::Result<bool>> + Send;
+
+    /// Releases a block whose execution failed, putting it back in the queue unless the maximum
+    /// number of retries is reached. The block can't be claimed again before `retry_delay`.
+    ///
+    /// Returns false if the worker doesn't own the block anymore.
+    fn release_failed_block(
+        &self,
+        block_number: u64,
+        worker_id: &str,
+        max_retries: usize,
+        error_class: ErrorClass,
+        retry_delay: Duration,
+    ) -> impl Future<Output = eyre::Result<bool>> + Send;
+
+    /// Marks a proven block claimed by the given worker as executed, and stores the proving
+    /// results.
+    ///
+    /// The lease is kept until it expires, so that the worker can still mark the block as
+    /// reorged.
+    ///
+    /// Returns false if the worker doesn't own the block anymore, in which case nothing is
+    /// stored.
+    fn update_block_proving(
+        &self,
+        block_number: u64,
+        worker_id: &str,
+        proving_duration: Duration,
+        proving_cycles: Option<u64>,
+    ) -> impl Future<Output = eyre::Result<bool>> + Send;
+
+    /// Marks an executed block claimed by the given worker as reorged, keeping the hash of the
+    /// orphaned block.
+    ///
+    /// Returns false if the worker doesn't own the block anymore.
+    fn update_block_status_as_reorged(
+        &self,
+        block_number: u64,
+        worker_id: &str,
+    ) -> impl Future<Output = eyre::Result<bool>> + Send;
+
+    /// Returns the highest block number successfully executed.
+    fn get_last_executed_block(&self) -> impl Future<Output = eyre::Result<Option<u64>>> + Send;
+
+    /// Returns the lowest block number from `from` that is in the table but not executed.
+    fn get_first_unexecuted_block(
+        &self,
+        from: u64,
+    ) -> impl Future<Output = eyre::Result<Option<u64>>> + Send;
+
+    /// Puts the running blocks of the `[from, to]` range whose lease expired or is missing back
+    /// in the queue.
+    ///
+    /// Their retry count is kept, and the blocks that failed deterministically are left alone.
+    ///
+    /// Returns the number of blocks put back in the queue.
+    fn requeue_stale_blocks(
+        &self,
+        from: u64,
+        to: u64,
+    ) -> impl Future<Output = eyre::Result<u64>> + Send;
+}
+
+/// The store selected at startup from the database URL.
+#[derive(Debug)]
+pub enum Store {
+    Postgres(PostgresStore),
+    Sqlite(SqliteStore),
+}
+
+impl Store {
+    /// Connects to the database, using SQLite if the URL starts with `sqlite:` and Postgres
+    /// otherwise.
+    ///
+    /// The store only sees the blocks of the given chain, so several chains can share the same
+    /// database.
+    pub async fn connect(database_url: &str, chain_id: u64) -> eyre::Result<Self> {
+        if database_url.starts_with("sqlite:") {
+            Ok(Store::Sqlite(SqliteStore::connect(database_url, chain_id).await?))
+        } else {
+            Ok(Store::Postgres(PostgresStore::connect(database_url, chain_id).await?))
+        }
+    }
+}
+
+impl BlockStore for Store {
+    async fn enqueue_block(&self, block_number: u64, block_hash: Option<B256>) -> eyre::Result<()> {
+        match self {
+            Store::Postgres(store) => store.enqueue_block(block_number, block_hash).await,
+            Store::Sqlite(store) => store.enqueue_block(block_number, block_hash).await,
+        }
+    }
+
+    async fn enqueue_blocks(&self, from: u64, to: u64) -> eyre::Result<u64> {
+        match self {
+            Store::Postgres(store) => store.enqueue_blocks(from, to).await,
+            Store::Sqlite(store) => store.enqueue_blocks(from, to).await,
+        }
+    }
+
+    async fn claim_block(
+        &self,
+        worker_id: &str,
+        lease_duration: Duration,
+        oldest_first: bool,
+    ) -> eyre::Result<Option<ClaimedBlock>> {
+        match self {
+            Store::Postgres(store) => {
+                store.claim_block(worker_id, lease_duration, oldest_first).await
+            }
+            Store::Sqlite(store) => {
+                store.claim_block(worker_id, lease_duration, oldest_first).await
+            }
+        }
+    }
+
+    async fn renew_lease(
+        &self,
+        block_number: u64,
+        worker_id: &str,
+        lease_duration: Duration,
+    ) -> eyre::Result<bool> {
+        match self {
+            Store::Postgres(store) => {
+                store.renew_lease(block_number, worker_id, lease_duration).await
+            }
+            Store::Sqlite(store) => {
+                store.renew_lease(block_number, worker_id, lease_duration).await
+            }
+        }
+    }
+
+    async fn update_block_status(
+        &self,
+        block_number: u64,
+        worker_id: &str,
+        block_hash: B256,
+        gas_used: u64,
+        tx_count: usize,
+        num_cycles: u64,
+        sp1_gas: u64,
+        status: ProvableBlockStatus,
+    ) -> eyre::Result<bool> {
+        match self {
+            Store::Postgres(store) => {
+                store
+                    .update_block_status(
+                        block_number,
+                        worker_id,
+                        block_hash,
+                        gas_used,
+                        tx_count,
+                        num_cycles,
+                        sp1_gas,
+                        status,
+                    )
+                    .await
+            }
+            Store::Sqlite(store) => {
+                store
+                    .update_block_status(
+                        block_number,
+                        worker_id,
+                        block_hash,
+                        gas_used,
+                        tx_count,
+                        num_cycles,
+                        sp1_gas,
+                        status,
+                    )
+                    .await
+            }
+        }
+    }
+
+    async fn release_failed_block(
+        &self,
+        block_number: u64,
+        worker_id: &str,
+        max_retries: usize,
+        error_class: ErrorClass,
+        retry_delay: Duration,
+    ) -> eyre::Result<bool> {
+        match self {
+            Store::Postgres(store) => {
+                store
+                    .release_failed_block(
+                        block_number,
+                        worker_id,
+                        max_retries,
+                        error_class,
+                        retry_delay,
+                    )
+                    .await
+            }
+            Store::Sqlite(store) => {
+                store
+                    .release_failed_block(
+                        block_number,
+                        worker_id,
+                        max_retries,
+                        error_class,
+                        retry_delay,
+                    )
+                    .await
+            }
+        }
+    }
+
+    async fn update_block_proving(
+        &self,
+        block_number: u64,
+        worker_id: &str,
+        proving_duration: Duration,
+        proving_cycles: Option<u64>,
+    ) -> eyre::Result<bool> {
+        match self {
+            Store::Postgres(store) => {
+                store
+                    .update_block_proving(block_number, worker_id, proving_duration, proving_cycles)
+                    .await
+            }
+            Store::Sqlite(store) => {
+                store
+                    .update_block_proving(block_number, worker_id, proving_duration, proving_cycles)
+                    .await
+            }
+        }
+    }
+
+    async fn update_block_status_as_reorged(
+        &self,
+        block_number: u64,
+        worker_id: &str,
+    ) -> eyre::Result<bool> {
+        match self {
+            Store::Postgres(store) => {
+                store.update_block_status_as_reorged(block_number, worker_id).await
+            }
+            Store::Sqlite(store) => {
+                store.update_block_status_as_reorged(block_number, worker_id).await
+            }
+        }
+    }
+
+    async fn get_last_executed_block(&self) -> eyre::Result<Option<u64>> {
+        match self {
+            Store::Postgres(store) => store.get_last_executed_block().await,
+            Store::Sqlite(store) => store.get_last_executed_block().await,
+        }
+    }
+
+    async fn get_first_unexecuted_block(&self, from: u64) -> eyre::Result<Option<u64>> {
+        match self {
+            Store::Postgres(store) => store.get_first_unexecuted_block(from).await,
+            Store::Sqlite(store) => store.get_first_unexecuted_block(from).await,
+        }
+    }
+
+    async fn requeue_stale_blocks(&self, from: u64, to: u64) -> eyre::Result<u64> {
+        match self {
+            Store::Postgres(store) => store.requeue_stale_blocks(from, to).await,
+            Store::Sqlite(store) => store.requeue_stale_blocks(from, to).await,
+        }
+    }
+}
+
+/// Persists the execution and proving results to the [Store].
+#[derive(Debug)]
+pub struct PersistToStore {
+    pub store: Arc<Store>,
+    /// The ID of the workers claiming the blocks, the results are only persisted while they own
+    /// the block.
+    pub worker_id: String,
+    /// Whether the blocks are proven after being executed.
+    pub proving: bool,
+    /// The directory where the proofs are written, if any.
+    pub proof_dir: Option<PathBuf>,
+}
+
+impl PersistToStore {
+    pub fn new(store: Arc<Store>, worker_id: String) -> Self {
+        Self { store, worker_id, proving: false, proof_dir: None }
+    }
+
+    /// Creates the hooks of an executor proving the blocks.
+    pub fn proving(store: Arc<Store>, worker_id: String, proof_dir: Option<PathBuf>) -> Self {
+        Self { store, worker_id, proving: true, proof_dir }
+    }
+}
+
+impl ExecutionHooks for PersistToStore {
+    async fn on_execution_end<P: NodePrimitives>(
+        &self,
+        executed_block: &Block<P::SignedTx>,
+        execution_report: &ExecutionReport,
+    ) -> eyre::Result<()> {
+        // Update the block status in the database
+        let updated = self
+            .store
+            .update_block_status(
+                executed_block.number(),
+                &self.worker_id,
+                executed_block.header.hash_slow(),
+                executed_block.header.gas_used(),
+                executed_block.body.transactions.len(),
+                execution_report.total_instruction_count(),
+                execution_report.gas.unwrap_or_default(),
+                // The block is marked as executed once proven.
+                if self.proving {
+                    ProvableBlockStatus::Running
+                } else {
+                    ProvableBlockStatus::Executed
+                },
+            )
+            .await?;
+
+        if !updated {
+            bail!("Lost the lease on block {}", executed_block.number());
+        }
+
+        Ok(())
+    }
+
+    async fn on_proving_end(
+        &self,
+        block_number: u64,
+        block_hash: B256,
+        proof: &SP1ProofWithPublicValues,
+        _proof_bytes: &[u8],
+        _vk: &SP1VerifyingKey,
+        cycle_count: Option<u64>,
+        proving_duration: Duration,
+    ) -> eyre::Result<()> {
+        if let Some(proof_dir) = &self.proof_dir {
+            save_proof(proof_dir, block_hash, proof)?;
+        }
+
+        let updated = self
+            .store
+            .update_block_proving(block_number, &self.worker_id, proving_duration, cycle_count)
+            .await?;
+
+        if !updated {
+            bail!("Lost the lease on block {block_number}");
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+pub struct ProvableBlock {
+    pub chain_id: i64,
+    pub block_number: i64,
+    pub block_hash: Option<String>,
+    pub status: ProvableBlockStatus,
+    pub gas_used: i64,
+    pub tx_count: i64,
+    pub num_cycles: i64,
+    pub start_time: Option<NaiveDateTime>,
+    pub end_time: Option<NaiveDateTime>,
+}
+
+impl ProvableBlock {
+    /// Creates a block waiting in the queue.
+    pub fn queued(chain_id: u64, block_number: u64, block_hash: Option<B256>) -> Self {
+        Self {
+            chain_id: chain_id as i64,
+            block_number: block_number as i64,
+            block_hash: block_hash.map(|h| h.to_string()),
+            status: ProvableBlockStatus::Queued,
+            gas_used: 0,
+            tx_count: 0,
+            num_cycles: 0,
+            start_time: None,
+            end_time: None,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum ProvableBlockStatus {
+    Queued,
+    /// The block has been claimed by a worker.
+    Running,
+    Executed,
+    Failed,
+    /// The block was executed, but is not part of the canonical chain anymore.
+    Reorged,
+}
+
+impl FromStr for ProvableBlockStatus {
+    type Err = eyre::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "queued" => Ok(ProvableBlockStatus::Queued),
+            "running" => Ok(ProvableBlockStatus::Running),
+            "executed" => Ok(ProvableBlockStatus::Executed),
+            "failed" => Ok(ProvableBlockStatus::Failed),
+            "reorged" => Ok(ProvableBlockStatus::Reorged),
+            _ => Err(eyre::eyre!("Unknown block status: {s}")),
+        }
+    }
+}
+
+impl Display for ProvableBlockStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ProvableBlockStatus::Queued => write!(f, "queued"),
+            ProvableBlockStatus::Running => write!(f, "running"),
+            ProvableBlockStatus::Executed => write!(f, "executed"),
+            ProvableBlockStatus::Failed => write!(f, "failed"),
+            ProvableBlockStatus::Reorged => write!(f, "reorged"),
+        }
+    }
+}
+
+/// A block claimed by a worker.
+#[derive(Debug)]
+pub struct ClaimedBlock {
+    pub block_number: u64,
+    pub retry_count: i32,
+}
+
+fn deadline(now: NaiveDateTime, duration: Duration) -> NaiveDateTime {
+    now + chrono::Duration::from_std(duration).expect("duration out of range")
+}
@@ -0,0 +1,530 @@
+use std::time::Duration;
+
+use alloy_primitives::B256;
+use sqlx::{postgres::PgPoolOptions, types::chrono::Utc, Pool, Postgres};
+
+use super::{deadline, BlockStore, ClaimedBlock, ProvableBlock, ProvableBlockStatus};
+use crate::retry::ErrorClass;
+
+/// Stores the block statuses of a chain in Postgres.
+#[derive(Debug)]
+pub struct PostgresStore {
+    pool: Pool<Postgres>,
+    chain_id: u64,
+}
+
+impl PostgresStore {
+    pub async fn connect(database_url: &str, chain_id: u64) -> Result<Self, sqlx::Error> {
+        Ok(Self { pool: build_db_pool(database_url).await?, chain_id })
+    }
+}
+
+impl BlockStore for PostgresStore {
+    async fn enqueue_block(&self, block_number: u64, block_hash: Option<B256>) -> eyre::Result<()> {
+        Ok(enqueue_block(&self.pool, self.chain_id, block_number, block_hash).await?)
+    }
+
+    async fn enqueue_blocks(&self, from: u64, to: u64) -> eyre::Result<u64> {
+        Ok(enqueue_blocks(&self.pool, self.chain_id, from, to).await?)
+    }
+
+    async fn claim_block(
+        &self,
+        worker_id: &str,
+        lease_duration: Duration,
+        oldest_first: bool,
+    ) -> eyre::Result<Option<ClaimedBlock>> {
+        Ok(claim_block(&self.pool, self.chain_id, worker_id, lease_duration, oldest_first).await?)
+    }
+
+    async fn renew_lease(
+        &self,
+        block_number: u64,
+        worker_id: &str,
+        lease_duration: Duration,
+    ) -> eyre::Result<bool> {
+        Ok(renew_lease(&self.pool, self.chain_id, block_number, worker_id, lease_duration).await?)
+    }
+
+    async fn update_block_status(
+        &self,
+        block_number: u64,
+        worker_id: &str,
+        block_hash: B256,
+        gas_used: u64,
+        tx_count: usize,
+        num_cycles: u64,
+        sp1_gas: u64,
+        status: ProvableBlockStatus,
+    ) -> eyre::Result<bool> {
+        Ok(update_block_status(
+            &self.pool,
+            self.chain_id,
+            block_number,
+            worker_id,
+            block_hash,
+            gas_used,
+            tx_count,
+            num_cycles,
+            sp1_gas,
+            status,
+        )
+        .await?)
+    }
+
+    async fn release_failed_block(
+        &self,
+        block_number: u64,
+        worker_id: &str,
+        max_retries: usize,
+        error_class: ErrorClass,
+        retry_delay: Duration,
+    ) -> eyre::Result<bool> {
+        Ok(release_failed_block(
+            &self.pool,
+            self.chain_id,
+            block_number,
+            worker_id,
+            max_retries,
+            error_class,
+            retry_delay,
+        )
+        .await?)
+    }
+
+    async fn update_block_proving(
+        &self,
+        block_number: u64,
+        worker_id: &str,
+        proving_duration: Duration,
+        proving_cycles: Option<u64>,
+    ) -> eyre::Result<bool> {
+        Ok(update_block_proving(
+            &self.pool,
+            self.chain_id,
+            block_number,
+            worker_id,
+            proving_duration,
+            proving_cycles,
+        )
+        .await?)
+    }
+
+    async fn update_block_status_as_reorged(
+        &self,
+        block_number: u64,
+        worker_id: &str,
+    ) -> eyre::Result<bool> {
+        Ok(update_block_status_as_reorged(&self.pool, self.chain_id, block_number, worker_id)
+            .await?)
+    }
+
+    async fn get_last_executed_block(&self) -> eyre::Result<Option<u64>> {
+        Ok(get_last_executed_block(&self.pool, self.chain_id).await?)
+    }
+
+    async fn get_first_unexecuted_block(&self, from: u64) -> eyre::Result<Option<u64>> {
+        Ok(get_first_unexecuted_block(&self.pool, self.chain_id, from).await?)
+    }
+
+    async fn requeue_stale_blocks(&self, from: u64, to: u64) -> eyre::Result<u64> {
+        Ok(requeue_stale_blocks(&self.pool, self.chain_id, from, to).await?)
+    }
+}
+
+async fn build_db_pool(database_url: &str) -> Result<Pool<Postgres>, sqlx::Error> {
+    PgPoolOptions::new().max_connections(64).connect(database_url).await
+}
+
+/// Adds a block to the queue, if it's not already there.
+///
+/// If the block hash is provided and the block was already executed with another hash, the
+/// block is marked as reorged so it's executed again.
+async fn enqueue_block(
+    pool: &Pool<Postgres>,
+    chain_id: u64,
+    block_number: u64,
+    block_hash: Option<B256>,
+) -> Result<(), sqlx::Error> {
+    let block = ProvableBlock::queued(chain_id, block_number, block_hash);
+
+    sqlx::query!(
+        r#"
+        INSERT INTO rsp_blocks
+        (chain_id, block_number, block_hash, status, gas_used, tx_count, num_cycles, start_time,
+            end_time)
+        VALUES ($11, $1, $2, $3, $4, $5, $6, $7, $8)
+        ON CONFLICT (chain_id, block_number)
+        DO UPDATE SET
+            status = $9,
+            retry_count = 0,
+            error_class = NULL,
+            next_attempt_at = NULL
+        WHERE rsp_blocks.status = $10
+            AND EXCLUDED.block_hash IS NOT NULL
+            AND rsp_blocks.block_hash IS DISTINCT FROM EXCLUDED.block_hash
+        "#,
+        block.block_number,
+        block.block_hash,
+        block.status.to_string(),
+        block.gas_used,
+        block.tx_count,
+        block.num_cycles,
+        block.start_time,
+        block.end_time,
+        ProvableBlockStatus::Reorged.to_string(),
+        ProvableBlockStatus::Executed.to_string(),
+        block.chain_id
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+/// Adds all the blocks in the `[from, to]` range that are not already there to the queue.
+///
+/// Returns the number of blocks added.
+async fn enqueue_blocks(
+    pool: &Pool<Postgres>,
+    chain_id: u64,
+    from: u64,
+    to: u64,
+) -> Result<u64, sqlx::Error> {
+    let result = sqlx::query!(
+        r#"
+        INSERT INTO rsp_blocks (chain_id, block_number, status, gas_used, tx_count, num_cycles)
+        SELECT $4, block_number, $3, 0, 0, 0
+        FROM generate_series($1::BIGINT, $2::BIGINT) AS block_number
+        ON CONFLICT (chain_id, block_number) DO NOTHING
+        "#,
+        from as i64,
+        to as i64,
+        ProvableBlockStatus::Queued.to_string(),
+        chain_id as i64
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected())
+}
+
+/// Claims the highest block available in the queue for the given worker.
+///
+/// Available blocks are the queued and reorged ones, and the running ones whose lease expired,
+/// meaning the worker that claimed them died. Blocks locked by concurrent claims are skipped.
+async fn claim_block(
+    pool: &Pool<Postgres>,
+    chain_id: u64,
+    worker_id: &str,
+    lease_duration: Duration,
+    oldest_first: bool,
+) -> Result<Option<ClaimedBlock>, sqlx::Error> {
+    let now = Utc::now().naive_utc();
+    let lease_expires_at = deadline(now, lease_duration);
+
+    let row = sqlx::query!(
+        r#"
+        UPDATE rsp_blocks
+        SET status = $1,
+            worker_id = $2,
+            lease_expires_at = $3,
+            start_time = $4,
+            end_time = NULL
+        WHERE chain_id = $8 AND block_number = (
+            SELECT block_number
+            FROM rsp_blocks
+            WHERE chain_id = $8 AND (
+                (status IN ($5, $6) AND (next_attempt_at IS NULL OR next_attempt_at <= $4))
+                OR (status = $7 AND lease_expires_at < $4)
+            )
+            ORDER BY CASE WHEN $9 THEN block_number ELSE -block_number END
+            LIMIT 1
+            FOR UPDATE SKIP LOCKED
+        )
+        RETURNING block_number, retry_count
+        "#,
+        ProvableBlockStatus::Running.to_string(),
+        worker_id,
+        lease_expires_at,
+        now,
+        ProvableBlockStatus::Queued.to_string(),
+        ProvableBlockStatus::Reorged.to_string(),
+        ProvableBlockStatus::Running.to_string(),
+        chain_id as i64,
+        oldest_first
+    )
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(row
+        .map(|r| ClaimedBlock { block_number: r.block_number as u64, retry_count: r.retry_count }))
+}
+
+/// Extends the lease of a block claimed by the given worker.
+///
+/// Returns false if the worker doesn't own the block anymore.
+async fn renew_lease(
+    pool: &Pool<Postgres>,
+    chain_id: u64,
+    block_number: u64,
+    worker_id: &str,
+    lease_duration: Duration,
+) -> Result<bool, sqlx::Error> {
+    let lease_expires_at = deadline(Utc::now().naive_utc(), lease_duration);
+
+    let result = sqlx::query!(
+        r#"
+        UPDATE rsp_blocks
+        SET lease_expires_at = $3
+        WHERE chain_id = $5 AND block_number = $1 AND worker_id = $2 AND status = $4
+        "#,
+        block_number as i64,
+        worker_id,
+        lease_expires_at,
+        ProvableBlockStatus::Running.to_string(),
+        chain_id as i64
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+/// Stores the execution results of a block claimed by the given worker, and updates its status.
+///
+/// Returns false if the worker doesn't own the block anymore.
+async fn update_block_status(
+    pool: &Pool<Postgres>,
+    chain_id: u64,
+    block_number: u64,
+    worker_id: &str,
+    block_hash: B256,
+    gas_used: u64,
+    tx_count: usize,
+    num_cycles: u64,
+    sp1_gas: u64,
+    status: ProvableBlockStatus,
+) -> Result<bool, sqlx::Error> {
+    let now = Utc::now().naive_utc();
+
+    let result = sqlx::query!(
+        r#"
+        UPDATE rsp_blocks
+        SET status = $5,
+            gas_used = $2,
+            tx_count = $3,
+            num_cycles = $4,
+            end_time = $6,
+            sp1_gas = $7,
+            block_hash = $8,
+            error_class = NULL,
+            next_attempt_at = NULL
+        WHERE chain_id = $9 AND block_number = $1 AND worker_id = $10 AND status = $11
+            AND lease_expires_at >= $6
+        "#,
+        block_number as i64,
+        gas_used as i64,
+        tx_count as i64,
+        num_cycles as i64,
+        status.to_string(),
+        now,
+        sp1_gas as i64,
+        block_hash.to_string(),
+        chain_id as i64,
+        worker_id,
+        ProvableBlockStatus::Running.to_string()
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+/// Releases a block whose execution failed, putting it back in the queue unless the maximum
+/// number of retries is reached. The block can't be claimed again before `retry_delay`.
+///
+/// Returns false if the worker doesn't own the block anymore.
+async fn release_failed_block(
+    pool: &Pool<Postgres>,
+    chain_id: u64,
+    block_number: u64,
+    worker_id: &str,
+    max_retries: usize,
+    error_class: ErrorClass,
+    retry_delay: Duration,
+) -> Result<bool, sqlx::Error> {
+    let now = Utc::now().naive_utc();
+    let result = sqlx::query!(
+        r#"
+        UPDATE rsp_blocks
+        SET retry_count = retry_count + 1,
+            status = CASE WHEN retry_count >= $3 THEN $4 ELSE $5 END,
+            error_class = $8,
+            next_attempt_at = $9,
+            lease_expires_at = NULL,
+            end_time = $6
+        WHERE chain_id = $10 AND block_number = $1 AND worker_id = $2 AND status = $7
+            AND lease_expires_at >= $6
+        "#,
+        block_number as i64,
+        worker_id,
+        max_retries as i32,
+        ProvableBlockStatus::Failed.to_string(),
+        ProvableBlockStatus::Queued.to_string(),
+        now,
+        ProvableBlockStatus::Running.to_string(),
+        error_class.to_string(),
+        deadline(now, retry_delay),
+        chain_id as i64
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+/// Marks a proven block claimed by the given worker as executed, and stores the proving results.
+///
+/// Returns false if the worker doesn't own the block anymore.
+async fn update_block_proving(
+    pool: &Pool<Postgres>,
+    chain_id: u64,
+    block_number: u64,
+    worker_id: &str,
+    proving_duration: Duration,
+    proving_cycles: Option<u64>,
+) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query!(
+        r#"
+        UPDATE rsp_blocks
+        SET status = $4,
+            proving_duration_ms = $2,
+            proving_cycles = $3,
+            end_time = $5
+        WHERE chain_id = $6 AND block_number = $1 AND worker_id = $7 AND status = $8
+            AND lease_expires_at >= $5
+        "#,
+        block_number as i64,
+        proving_duration.as_millis() as i64,
+        proving_cycles.map(|c| c as i64),
+        ProvableBlockStatus::Executed.to_string(),
+        Utc::now().naive_utc(),
+        chain_id as i64,
+        worker_id,
+        ProvableBlockStatus::Running.to_string()
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+/// Marks an executed block claimed by the given worker as reorged, keeping the hash of the
+/// orphaned block.
+///
+/// Returns false if the worker doesn't own the block anymore.
+async fn update_block_status_as_reorged(
+    pool: &Pool<Postgres>,
+    chain_id: u64,
+    block_number: u64,
+    worker_id: &str,
+) -> Result<bool, sqlx::Error> {
+    let now = Utc::now().naive_utc();
+    let result = sqlx::query!(
+        r#"
+        UPDATE rsp_blocks
+        SET status = $2,
+            end_time = $3
+        WHERE chain_id = $5 AND block_number = $1 AND status = $4 AND worker_id = $6
+            AND lease_expires_at >= $3
+        "#,
+        block_number as i64,
+        ProvableBlockStatus::Reorged.to_string(),
+        now,
+        ProvableBlockStatus::Executed.to_string(),
+        chain_id as i64,
+        worker_id
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+/// Returns the highest block number successfully executed.
+async fn get_last_executed_block(
+    pool: &Pool<Postgres>,
+    chain_id: u64,
+) -> Result<Option<u64>, sqlx::Error> {
+    let row = sqlx::query!(
+        r#"
+        SELECT MAX(block_number) AS block_number
+        FROM rsp_blocks
+        WHERE chain_id = $2 AND status = $1
+        "#,
+        ProvableBlockStatus::Executed.to_string(),
+        chain_id as i64
+    )
+    .fetch_one(pool)
+    .await?;
+
+    Ok(row.block_number.map(|n| n as u64))
+}
+
+/// Returns the lowest block number from `from` that is in the table but not executed.
+async fn get_first_unexecuted_block(
+    pool: &Pool<Postgres>,
+    chain_id: u64,
+    from: u64,
+) -> Result<Option<u64>, sqlx::Error> {
+    let row = sqlx::query!(
+        r#"
+        SELECT MIN(block_number) AS block_number
+        FROM rsp_blocks
+        WHERE chain_id = $3 AND block_number >= $1 AND status != $2
+        "#,
+        from as i64,
+        ProvableBlockStatus::Executed.to_string(),
+        chain_id as i64
+    )
+    .fetch_one(pool)
+    .await?;
+
+    Ok(row.block_number.map(|n| n as u64))
+}
+
+/// Puts the running blocks of the `[from, to]` range whose lease expired or is missing back in
+/// the queue.
+///
+/// Their retry count is kept, and the blocks that failed deterministically are left alone.
+async fn requeue_stale_blocks(
+    pool: &Pool<Postgres>,
+    chain_id: u64,
+    from: u64,
+    to: u64,
+) -> Result<u64, sqlx::Error> {
+    let result = sqlx::query!(
+        r#"
+        UPDATE rsp_blocks
+        SET status = $3,
+            worker_id = NULL,
+            lease_expires_at = NULL
+        WHERE chain_id = $7 AND block_number BETWEEN $1 AND $2
+            AND status = $4
+            AND (lease_expires_at IS NULL OR lease_expires_at < $5)
+            AND (error_class IS NULL OR error_class != $6)
+        "#,
+        from as i64,
+        to as i64,
+        ProvableBlockStatus::Queued.to_string(),
+        ProvableBlockStatus::Running.to_string(),
+        Utc::now().naive_utc(),
+        ErrorClass::Deterministic.to_string(),
+        chain_id as i64
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected())
+}
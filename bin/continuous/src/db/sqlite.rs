@@ -0,0 +1,650 @@
+use std::{str::FromStr, time::Duration};
+
+use alloy_primitives::B256;
+use sqlx::{
+    sqlite::{SqliteConnectOptions, SqlitePoolOptions},
+    types::chrono::Utc,
+    Pool, Row, Sqlite,
+};
+
+use super::{deadline, BlockStore, ClaimedBlock, ProvableBlock, ProvableBlockStatus};
+use crate::retry::ErrorClass;
+
+/// Stores the block statuses of a chain in a SQLite database, for single host deployments.
+///
+/// SQLite serializes the writes, so the claims don't need row locking to be exclusive.
+#[derive(Debug)]
+pub struct SqliteStore {
+    pool: Pool<Sqlite>,
+    chain_id: u64,
+}
+
+impl SqliteStore {
+    /// Opens the database, creating it if needed, and applies the migrations.
+    pub async fn connect(database_url: &str, chain_id: u64) -> Result<Self, sqlx::Error> {
+        let options = SqliteConnectOptions::from_str(database_url)?.create_if_missing(true);
+
+        // Each connection to an in-memory database gets its own database.
+        let max_connections = if database_url.contains(":memory:") { 1 } else { 8 };
+
+        let pool =
+            SqlitePoolOptions::new().max_connections(max_connections).connect_with(options).await?;
+
+        sqlx::migrate!("./migrations_sqlite").run(&pool).await?;
+
+        Ok(Self { pool, chain_id })
+    }
+}
+
+impl BlockStore for SqliteStore {
+    async fn enqueue_block(&self, block_number: u64, block_hash: Option<B256>) -> eyre::Result<()> {
+        let block = ProvableBlock::queued(self.chain_id, block_number, block_hash);
+
+        sqlx::query(
+            r#"
+            INSERT INTO rsp_blocks
+            (chain_id, block_number, block_hash, status, gas_used, tx_count, num_cycles, start_time,
+                end_time)
+            VALUES (?11, ?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)
+            ON CONFLICT (chain_id, block_number)
+            DO UPDATE SET
+                status = ?9,
+                retry_count = 0,
+                error_class = NULL,
+                next_attempt_at = NULL
+            WHERE rsp_blocks.status = ?10
+                AND excluded.block_hash IS NOT NULL
+                AND rsp_blocks.block_hash IS NOT excluded.block_hash
+            "#,
+        )
+        .bind(block.block_number)
+        .bind(block.block_hash)
+        .bind(block.status.to_string())
+        .bind(block.gas_used)
+        .bind(block.tx_count)
+        .bind(block.num_cycles)
+        .bind(block.start_time)
+        .bind(block.end_time)
+        .bind(ProvableBlockStatus::Reorged.to_string())
+        .bind(ProvableBlockStatus::Executed.to_string())
+        .bind(block.chain_id)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn enqueue_blocks(&self, from: u64, to: u64) -> eyre::Result<u64> {
+        if from > to {
+            return Ok(0);
+        }
+
+        let result = sqlx::query(
+            r#"
+            WITH RECURSIVE series(block_number) AS (
+                SELECT ?1
+                UNION ALL
+                SELECT block_number + 1 FROM series WHERE block_number < ?2
+            )
+            INSERT INTO rsp_blocks (chain_id, block_number, status, gas_used, tx_count, num_cycles)
+            SELECT ?4, block_number, ?3, 0, 0, 0
+            FROM series
+            WHERE true
+            ON CONFLICT (chain_id, block_number) DO NOTHING
+            "#,
+        )
+        .bind(from as i64)
+        .bind(to as i64)
+        .bind(ProvableBlockStatus::Queued.to_string())
+        .bind(self.chain_id as i64)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected())
+    }
+
+    async fn claim_block(
+        &self,
+        worker_id: &str,
+        lease_duration: Duration,
+        oldest_first: bool,
+    ) -> eyre::Result<Option<ClaimedBlock>> {
+        let now = Utc::now().naive_utc();
+        let lease_expires_at = deadline(now, lease_duration);
+
+        let row = sqlx::query(
+            r#"
+            UPDATE rsp_blocks
+            SET status = ?1,
+                worker_id = ?2,
+                lease_expires_at = ?3,
+                start_time = ?4,
+                end_time = NULL
+            WHERE chain_id = ?8 AND block_number = (
+                SELECT block_number
+                FROM rsp_blocks
+                WHERE chain_id = ?8 AND (
+                    (status IN (?5, ?6) AND (next_attempt_at IS NULL OR next_attempt_at <= ?4))
+                    OR (status = ?7 AND lease_expires_at < ?4)
+                )
+                ORDER BY CASE WHEN ?9 THEN block_number ELSE -block_number END
+                LIMIT 1
+            )
+            RETURNING block_number, retry_count
+            "#,
+        )
+        .bind(ProvableBlockStatus::Running.to_string())
+        .bind(worker_id)
+        .bind(lease_expires_at)
+        .bind(now)
+        .bind(ProvableBlockStatus::Queued.to_string())
+        .bind(ProvableBlockStatus::Reorged.to_string())
+        .bind(ProvableBlockStatus::Running.to_string())
+        .bind(self.chain_id as i64)
+        .bind(oldest_first)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        row.map(|r| {
+            Ok(ClaimedBlock {
+                block_number: r.try_get::<i64, _>("block_number")? as u64,
+                retry_count: r.try_get("retry_count")?,
+            })
+        })
+        .transpose()
+    }
+
+    async fn renew_lease(
+        &self,
+        block_number: u64,
+        worker_id: &str,
+        lease_duration: Duration,
+    ) -> eyre::Result<bool> {
+        let lease_expires_at = deadline(Utc::now().naive_utc(), lease_duration);
+
+        let result = sqlx::query(
+            r#"
+            UPDATE rsp_blocks
+            SET lease_expires_at = ?3
+            WHERE chain_id = ?5 AND block_number = ?1 AND worker_id = ?2 AND status = ?4
+            "#,
+        )
+        .bind(block_number as i64)
+        .bind(worker_id)
+        .bind(lease_expires_at)
+        .bind(ProvableBlockStatus::Running.to_string())
+        .bind(self.chain_id as i64)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    async fn update_block_status(
+        &self,
+        block_number: u64,
+        worker_id: &str,
+        block_hash: B256,
+        gas_used: u64,
+        tx_count: usize,
+        num_cycles: u64,
+        sp1_gas: u64,
+        status: ProvableBlockStatus,
+    ) -> eyre::Result<bool> {
+        let now = Utc::now().naive_utc();
+
+        let result = sqlx::query(
+            r#"
+            UPDATE rsp_blocks
+            SET status = ?5,
+                gas_used = ?2,
+                tx_count = ?3,
+                num_cycles = ?4,
+                end_time = ?6,
+                sp1_gas = ?7,
+                block_hash = ?8,
+                error_class = NULL,
+                next_attempt_at = NULL
+            WHERE chain_id = ?9 AND block_number = ?1 AND worker_id = ?10 AND status = ?11
+                AND lease_expires_at >= ?6
+            "#,
+        )
+        .bind(block_number as i64)
+        .bind(gas_used as i64)
+        .bind(tx_count as i64)
+        .bind(num_cycles as i64)
+        .bind(status.to_string())
+        .bind(now)
+        .bind(sp1_gas as i64)
+        .bind(block_hash.to_string())
+        .bind(self.chain_id as i64)
+        .bind(worker_id)
+        .bind(ProvableBlockStatus::Running.to_string())
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    async fn release_failed_block(
+        &self,
+        block_number: u64,
+        worker_id: &str,
+        max_retries: usize,
+        error_class: ErrorClass,
+        retry_delay: Duration,
+    ) -> eyre::Result<bool> {
+        let now = Utc::now().naive_utc();
+
+        let result = sqlx::query(
+            r#"
+            UPDATE rsp_blocks
+            SET retry_count = retry_count + 1,
+                status = CASE WHEN retry_count >= ?3 THEN ?4 ELSE ?5 END,
+                error_class = ?8,
+                next_attempt_at = ?9,
+                lease_expires_at = NULL,
+                end_time = ?6
+            WHERE chain_id = ?10 AND block_number = ?1 AND worker_id = ?2 AND status = ?7
+                AND lease_expires_at >= ?6
+            "#,
+        )
+        .bind(block_number as i64)
+        .bind(worker_id)
+        .bind(max_retries as i64)
+        .bind(ProvableBlockStatus::Failed.to_string())
+        .bind(ProvableBlockStatus::Queued.to_string())
+        .bind(now)
+        .bind(ProvableBlockStatus::Running.to_string())
+        .bind(error_class.to_string())
+        .bind(deadline(now, retry_delay))
+        .bind(self.chain_id as i64)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    async fn update_block_proving(
+        &self,
+        block_number: u64,
+        worker_id: &str,
+        proving_duration: Duration,
+        proving_cycles: Option<u64>,
+    ) -> eyre::Result<bool> {
+        let result = sqlx::query(
+            r#"
+            UPDATE rsp_blocks
+            SET status = ?4,
+                proving_duration_ms = ?2,
+                proving_cycles = ?3,
+                end_time = ?5
+            WHERE chain_id = ?6 AND block_number = ?1 AND worker_id = ?7 AND status = ?8
+                AND lease_expires_at >= ?5
+            "#,
+        )
+        .bind(block_number as i64)
+        .bind(proving_duration.as_millis() as i64)
+        .bind(proving_cycles.map(|c| c as i64))
+        .bind(ProvableBlockStatus::Executed.to_string())
+        .bind(Utc::now().naive_utc())
+        .bind(self.chain_id as i64)
+        .bind(worker_id)
+        .bind(ProvableBlockStatus::Running.to_string())
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    async fn update_block_status_as_reorged(
+        &self,
+        block_number: u64,
+        worker_id: &str,
+    ) -> eyre::Result<bool> {
+        let now = Utc::now().naive_utc();
+
+        let result = sqlx::query(
+            r#"
+            UPDATE rsp_blocks
+            SET status = ?2,
+                end_time = ?3
+            WHERE chain_id = ?5 AND block_number = ?1 AND status = ?4 AND worker_id = ?6
+                AND lease_expires_at >= ?3
+            "#,
+        )
+        .bind(block_number as i64)
+        .bind(ProvableBlockStatus::Reorged.to_string())
+        .bind(now)
+        .bind(ProvableBlockStatus::Executed.to_string())
+        .bind(self.chain_id as i64)
+        .bind(worker_id)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    async fn get_last_executed_block(&self) -> eyre::Result<Option<u64>> {
+        let row = sqlx::query(
+            r#"
+            SELECT MAX(block_number) AS block_number
+            FROM rsp_blocks
+            WHERE chain_id = ?2 AND status = ?1
+            "#,
+        )
+        .bind(ProvableBlockStatus::Executed.to_string())
+        .bind(self.chain_id as i64)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(row.try_get::<Option<i64>, _>("block_number")?.map(|n| n as u64))
+    }
+
+    async fn get_first_unexecuted_block(&self, from: u64) -> eyre::Result<Option<u64>> {
+        let row = sqlx::query(
+            r#"
+            SELECT MIN(block_number) AS block_number
+            FROM rsp_blocks
+            WHERE chain_id = ?3 AND block_number >= ?1 AND status != ?2
+            "#,
+        )
+        .bind(from as i64)
+        .bind(ProvableBlockStatus::Executed.to_string())
+        .bind(self.chain_id as i64)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(row.try_get::<Option<i64>, _>("block_number")?.map(|n| n as u64))
+    }
+
+    async fn requeue_stale_blocks(&self, from: u64, to: u64) -> eyre::Result<u64> {
+        let result = sqlx::query(
+            r#"
+            UPDATE rsp_blocks
+            SET status = ?3,
+                worker_id = NULL,
+                lease_expires_at = NULL
+            WHERE chain_id = ?7 AND block_number BETWEEN ?1 AND ?2
+                AND status = ?4
+                AND (lease_expires_at IS NULL OR lease_expires_at < ?5)
+                AND (error_class IS NULL OR error_class != ?6)
+            "#,
+        )
+        .bind(from as i64)
+        .bind(to as i64)
+        .bind(ProvableBlockStatus::Queued.to_string())
+        .bind(ProvableBlockStatus::Running.to_string())
+        .bind(Utc::now().naive_utc())
+        .bind(ErrorClass::Deterministic.to_string())
+        .bind(self.chain_id as i64)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn memory_store() -> SqliteStore {
+        SqliteStore::connect("sqlite::memory:", 1).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_claims_highest_queued_block() {
+        let store = memory_store().await;
+
+        assert_eq!(store.enqueue_blocks(10, 12).await.unwrap(), 3);
+        assert_eq!(store.enqueue_blocks(11, 13).await.unwrap(), 1);
+        assert_eq!(store.enqueue_blocks(13, 12).await.unwrap(), 0);
+
+        let lease = Duration::from_secs(60);
+        let claimed = store.claim_block("worker-1", lease, false).await.unwrap().unwrap();
+        assert_eq!(claimed.block_number, 13);
+        assert_eq!(claimed.retry_count, 0);
+
+        let claimed = store.claim_block("worker-2", lease, false).await.unwrap().unwrap();
+        assert_eq!(claimed.block_number, 12);
+
+        assert!(store.renew_lease(13, "worker-1", lease).await.unwrap());
+        assert!(!store.renew_lease(13, "worker-2", lease).await.unwrap());
+
+        let update_block_status = |worker_id| {
+            store.update_block_status(
+                13,
+                worker_id,
+                B256::ZERO,
+                21_000,
+                1,
+                100,
+                200,
+                ProvableBlockStatus::Executed,
+            )
+        };
+        assert!(!update_block_status("worker-2").await.unwrap());
+        assert_eq!(store.get_last_executed_block().await.unwrap(), None);
+
+        assert!(update_block_status("worker-1").await.unwrap());
+        assert_eq!(store.get_last_executed_block().await.unwrap(), Some(13));
+
+        // The block is not running anymore.
+        assert!(!update_block_status("worker-1").await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_isolates_chains() {
+        let dir = std::env::temp_dir().join(format!("rsp-continuous-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let url = format!("sqlite://{}", dir.join("rsp.db").display());
+
+        let mainnet = SqliteStore::connect(&url, 1).await.unwrap();
+        let op_mainnet = SqliteStore::connect(&url, 10).await.unwrap();
+
+        assert_eq!(mainnet.enqueue_blocks(1, 2).await.unwrap(), 2);
+        assert_eq!(op_mainnet.enqueue_blocks(1, 3).await.unwrap(), 3);
+
+        let lease = Duration::from_secs(60);
+        assert_eq!(
+            mainnet.claim_block("worker", lease, false).await.unwrap().unwrap().block_number,
+            2
+        );
+        assert_eq!(
+            op_mainnet.claim_block("worker", lease, false).await.unwrap().unwrap().block_number,
+            3
+        );
+
+        mainnet.pool.close().await;
+        op_mainnet.pool.close().await;
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_marks_proven_block_as_executed() {
+        let store = memory_store().await;
+
+        store.enqueue_block(1, None).await.unwrap();
+        store.claim_block("worker", Duration::from_secs(60), false).await.unwrap().unwrap();
+
+        assert!(store
+            .update_block_status(1, "worker", B256::ZERO, 0, 0, 0, 0, ProvableBlockStatus::Running)
+            .await
+            .unwrap());
+        assert_eq!(store.get_last_executed_block().await.unwrap(), None);
+
+        let proving_duration = Duration::from_secs(10);
+        assert!(!store
+            .update_block_proving(1, "other-worker", proving_duration, Some(1000))
+            .await
+            .unwrap());
+        assert!(store
+            .update_block_proving(1, "worker", proving_duration, Some(1000))
+            .await
+            .unwrap());
+        assert_eq!(store.get_last_executed_block().await.unwrap(), Some(1));
+    }
+
+    #[tokio::test]
+    async fn test_fails_block_after_max_retries() {
+        let store = memory_store().await;
+        let lease = Duration::from_secs(60);
+
+        store.enqueue_block(1, None).await.unwrap();
+
+        for retry_count in 0..2 {
+            let claimed = store.claim_block("worker", lease, false).await.unwrap().unwrap();
+            assert_eq!(claimed.retry_count, retry_count);
+
+            assert!(!store
+                .release_failed_block(1, "other-worker", 1, ErrorClass::Resource, Duration::ZERO)
+                .await
+                .unwrap());
+            assert!(store
+                .release_failed_block(1, "worker", 1, ErrorClass::Resource, Duration::ZERO)
+                .await
+                .unwrap());
+        }
+
+        // The block failed for good.
+        assert!(store.claim_block("worker", lease, false).await.unwrap().is_none());
+        assert!(!store
+            .release_failed_block(1, "worker", 1, ErrorClass::Resource, Duration::ZERO)
+            .await
+            .unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_delays_retry() {
+        let store = memory_store().await;
+        let lease = Duration::from_secs(60);
+
+        store.enqueue_blocks(1, 2).await.unwrap();
+
+        store.claim_block("worker", lease, false).await.unwrap().unwrap();
+        assert!(store
+            .release_failed_block(2, "worker", 3, ErrorClass::Transient, lease)
+            .await
+            .unwrap());
+
+        // Block 2 is waiting for its retry delay, block 1 is claimed instead.
+        let claimed = store.claim_block("worker", lease, false).await.unwrap().unwrap();
+        assert_eq!(claimed.block_number, 1);
+        assert!(store.claim_block("worker", lease, false).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_reclaims_expired_lease() {
+        let store = memory_store().await;
+
+        store.enqueue_block(1, None).await.unwrap();
+
+        let claimed = store.claim_block("dead-worker", Duration::ZERO, false).await.unwrap();
+        assert!(claimed.is_some());
+
+        tokio::time::sleep(Duration::from_millis(10)).await;
+
+        let claimed =
+            store.claim_block("worker", Duration::from_secs(60), false).await.unwrap().unwrap();
+        assert_eq!(claimed.block_number, 1);
+        assert!(!store.renew_lease(1, "dead-worker", Duration::ZERO).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_claims_oldest_queued_block() {
+        let store = memory_store().await;
+        let lease = Duration::from_secs(60);
+
+        store.enqueue_blocks(10, 12).await.unwrap();
+
+        assert_eq!(
+            store.claim_block("worker", lease, true).await.unwrap().unwrap().block_number,
+            10
+        );
+        assert_eq!(
+            store.claim_block("worker", lease, false).await.unwrap().unwrap().block_number,
+            12
+        );
+        assert_eq!(
+            store.claim_block("worker", lease, true).await.unwrap().unwrap().block_number,
+            11
+        );
+    }
+
+    #[tokio::test]
+    async fn test_requeues_stale_blocks() {
+        let store = memory_store().await;
+        let lease = Duration::from_secs(60);
+
+        store.enqueue_blocks(1, 5).await.unwrap();
+
+        // Block 5 is executed, block 4 failed, block 3 is running, block 2 is running without a
+        // lease and block 1 stays queued.
+        store.claim_block("worker", lease, false).await.unwrap().unwrap();
+        store
+            .update_block_status(5, "worker", B256::ZERO, 0, 0, 0, 0, ProvableBlockStatus::Executed)
+            .await
+            .unwrap();
+        store.claim_block("worker", lease, false).await.unwrap().unwrap();
+        store
+            .release_failed_block(4, "worker", 0, ErrorClass::Deterministic, Duration::ZERO)
+            .await
+            .unwrap();
+        store.claim_block("worker", lease, false).await.unwrap().unwrap();
+        store.claim_block("worker", lease, false).await.unwrap().unwrap();
+        sqlx::query(
+            "UPDATE rsp_blocks SET retry_count = 2, lease_expires_at = NULL WHERE block_number = 2",
+        )
+        .execute(&store.pool)
+        .await
+        .unwrap();
+
+        assert_eq!(store.get_first_unexecuted_block(0).await.unwrap(), Some(1));
+        assert_eq!(store.get_first_unexecuted_block(5).await.unwrap(), None);
+
+        // Block 3 is still leased and block 4 failed for good, only block 2 is stale.
+        assert_eq!(store.requeue_stale_blocks(1, 5).await.unwrap(), 1);
+        let claimed = store.claim_block("worker", lease, false).await.unwrap().unwrap();
+        assert_eq!(claimed.block_number, 2);
+        assert_eq!(claimed.retry_count, 2);
+    }
+
+    #[tokio::test]
+    async fn test_requeues_reorged_block() {
+        let store = memory_store().await;
+        let lease = Duration::from_secs(60);
+
+        store.enqueue_block(1, Some(B256::ZERO)).await.unwrap();
+        store.claim_block("worker", lease, false).await.unwrap().unwrap();
+        store
+            .update_block_status(1, "worker", B256::ZERO, 0, 0, 0, 0, ProvableBlockStatus::Executed)
+            .await
+            .unwrap();
+
+        // Same hash, nothing to do.
+        store.enqueue_block(1, Some(B256::ZERO)).await.unwrap();
+        assert!(store.claim_block("worker", lease, false).await.unwrap().is_none());
+
+        store.enqueue_block(1, Some(B256::with_last_byte(1))).await.unwrap();
+        let claimed = store.claim_block("worker", lease, false).await.unwrap().unwrap();
+        assert_eq!(claimed.block_number, 1);
+
+        store
+            .update_block_status(
+                1,
+                "worker",
+                B256::with_last_byte(1),
+                0,
+                0,
+                0,
+                0,
+                ProvableBlockStatus::Executed,
+            )
+            .await
+            .unwrap();
+        assert!(!store.update_block_status_as_reorged(1, "other-worker").await.unwrap());
+        assert!(store.update_block_status_as_reorged(1, "worker").await.unwrap());
+        assert_eq!(store.get_last_executed_block().await.unwrap(), None);
+    }
+}
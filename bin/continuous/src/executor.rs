@@ -0,0 +1,100 @@
+use std::sync::Arc;
+
+use alloy_primitives::B256;
+use alloy_provider::{network::Ethereum, RootProvider};
+use either::Either;
+use op_alloy_network::Optimism;
+use rsp_host_executor::{
+    create_eth_block_execution_strategy_factory, create_op_block_execution_strategy_factory,
+    remote_prover::RemoteProver, BlockExecutor, Config, EthExecutorComponents, FullExecutor,
+    OpExecutorComponents,
+};
+use rsp_provider::create_provider;
+use sp1_sdk::{include_elf, EnvProver, SP1VerifyingKey};
+use url::Url;
+
+use crate::db::PersistToStore;
+
+/// The local prover, or a remote proving service when configured.
+pub type EitherProver = Either<EnvProver, RemoteProver>;
+
+/// Executes the blocks of either an Ethereum or an OP Stack chain.
+pub enum Executor {
+    Eth(FullExecutor<EthExecutorComponents<PersistToStore, EitherProver>, RootProvider<Ethereum>>),
+    Op(FullExecutor<OpExecutorComponents<PersistToStore, EitherProver>, RootProvider<Optimism>>),
+}
+
+impl Executor {
+    /// Creates the executor matching the chain of the config.
+    pub async fn try_new(
+        http_rpc_url: Url,
+        prover_client: Arc<EitherProver>,
+        hooks: PersistToStore,
+        config: Config,
+    ) -> eyre::Result<Self> {
+        if config.chain.is_optimism() {
+            let elf = include_elf!("rsp-client-op").to_vec();
+            let block_execution_strategy_factory =
+                create_op_block_execution_strategy_factory(&config.genesis);
+
+            let executor = FullExecutor::try_new(
+                create_provider(http_rpc_url),
+                elf,
+                block_execution_strategy_factory,
+                prover_client,
+                hooks,
+                config,
+            )
+            .await?;
+
+            Ok(Executor::Op(executor))
+        } else {
+            let elf = include_elf!("rsp-client").to_vec();
+            let block_execution_strategy_factory = create_eth_block_execution_strategy_factory(
+                &config.genesis,
+                config.custom_beneficiary,
+            );
+
+            let executor = FullExecutor::try_new(
+                create_provider(http_rpc_url),
+                elf,
+                block_execution_strategy_factory,
+                prover_client,
+                hooks,
+                config,
+            )
+            .await?;
+
+            Ok(Executor::Eth(executor))
+        }
+    }
+
+    pub async fn wait_for_block(&self, block_number: u64) -> eyre::Result<()> {
+        match self {
+            Executor::Eth(executor) => executor.wait_for_block(block_number).await,
+            Executor::Op(executor) => executor.wait_for_block(block_number).await,
+        }
+    }
+
+    /// Executes a block, returning its hash.
+    pub async fn execute(&self, block_number: u64) -> eyre::Result<B256> {
+        match self {
+            Executor::Eth(executor) => executor.execute(block_number.into()).await,
+            Executor::Op(executor) => executor.execute(block_number.into()).await,
+        }
+    }
+
+    pub async fn is_canonical(&self, block_number: u64, block_hash: B256) -> eyre::Result<bool> {
+        match self {
+            Executor::Eth(executor) => executor.is_canonical(block_number, block_hash).await,
+            Executor::Op(executor) => executor.is_canonical(block_number, block_hash).await,
+        }
+    }
+
+    pub fn vk(&self) -> Arc<SP1VerifyingKey> {
+        match self {
+            Executor::Eth(executor) => executor.vk(),
+            Executor::Op(executor) => executor.vk(),
+        }
+    }
+}
@@ -1,24 +1,39 @@
-use std::sync::Arc;
+use std::{sync::Arc, time::Duration};
 
-use alloy_provider::{network::Ethereum, Provider, ProviderBuilder, WsConnect};
+use alloy_provider::{network::Ethereum, Provider, ProviderBuilder, RootProvider, WsConnect};
 use clap::Parser;
 use cli::Args;
-use db::PersistToPostgres;
+use db::{BlockStore, PersistToStore, Store};
+use either::Either;
+use executor::Executor;
 use futures_util::StreamExt;
-use rsp_host_executor::{
-    alerting::AlertingClient, create_eth_block_execution_strategy_factory, BlockExecutor, Config,
-    EthExecutorComponents, ExecutorComponents, FullExecutor,
-};
+use proving::{save_vk, Proving};
+use rsp_host_executor::{alerting::AlertingClient, remote_prover::RemoteProver, Config};
 use rsp_provider::create_provider;
-use sp1_sdk::{include_elf, EnvProver};
-use tokio::{sync::Semaphore, task};
-use tracing::{error, info, instrument, warn};
+use sp1_sdk::{EnvProver, ProverClient};
+use tokio::{sync::Semaphore, task, time::sleep};
+use tracing::{error, info, warn};
 use tracing_subscriber::{fmt, layer::SubscriberExt, util::SubscriberInitExt, EnvFilter};
+use worker::Worker;
 
 mod db;
 
 mod cli;
 
+mod executor;
+
+mod proving;
+
+mod retry;
+
+mod worker;
+
+/// The delay before the first resubscription attempt, doubled on each consecutive failure.
+const RESUBSCRIBE_MIN_BACKOFF: Duration = Duration::from_secs(1);
+
+/// The maximum delay between two resubscription attempts.
+const RESUBSCRIBE_MAX_BACKOFF: Duration = Duration::from_secs(60);
+
 #[tokio::main]
 async fn main() -> eyre::Result<()> {
     // Initialize the environment variables.
@@ -40,113 +55,192 @@ async fn main() -> eyre::Result<()> {
         .init();
 
     let args = Args::parse();
-    let config = Config::mainnet();
+    let http_provider = create_provider(args.http_rpc_url.clone());
+    let chain_id = match args.chain_id {
+        Some(chain_id) => chain_id,
+        None => http_provider.get_chain_id().await?,
+    };
+    let config = args.as_config(chain_id)?;
+
+    info!("Monitoring chain {}", config.chain);
+
+    let store = Arc::new(Store::connect(&args.database_url, chain_id).await?);
+    let worker_id = args.worker_id();
+    let alerting_client = AlertingClient::from_options(
+        args.pager_duty_integration_key.clone(),
+        args.pager_duty_endpoint.as_ref(),
+        args.alert_webhook_url.clone(),
+        args.log_alerts,
+    )
+    .map(Arc::new);
+    let prover_client = Arc::new(match &args.remote_prover_url {
+        Some(url) => Either::Right(
+            RemoteProver::new(url.clone(), ProverClient::builder().cpu().build())
+                .with_timeout(Duration::from_secs(args.remote_prover_timeout)),
+        ),
+        None => Either::Left(EnvProver::new()),
+    });
+
+    let proving = match args.proving_policy() {
+        Some(policy) => {
+            // The proofs of each chain are kept apart, as several chains can share a directory.
+            let proof_dir = args.proof_dir.as_ref().map(|dir| dir.join(chain_id.to_string()));
+            let executor = Executor::try_new(
+                args.http_rpc_url.clone(),
+                prover_client.clone(),
+                PersistToStore::proving(store.clone(), worker_id.clone(), proof_dir.clone()),
+                Config {
+                    prove_mode: Some(args.proof_mode),
+                    verify_proof: args.verify_proof,
+                    ..args.as_config(chain_id)?
+                },
+            )
+            .await?;
+
+            if let Some(proof_dir) = &proof_dir {
+                save_vk(proof_dir, &executor.vk())?;
+            }
 
-    let elf = include_elf!("rsp-client").to_vec();
-    let block_execution_strategy_factory =
-        create_eth_block_execution_strategy_factory(&config.genesis, None);
+            info!(
+                ?policy,
+                "Proving enabled, with {} concurrent proofs", args.max_concurrent_proofs
+            );
 
-    let db_pool = db::build_db_pool(&args.database_url).await?;
-    let ws = WsConnect::new(args.ws_rpc_url);
-    let ws_provider = ProviderBuilder::new().connect_ws(ws).await?;
-    let http_provider = create_provider(args.http_rpc_url);
-    let alerting_client =
-        args.pager_duty_integration_key.map(|key| Arc::new(AlertingClient::new(key)));
-    let prover_client = Arc::new(EnvProver::new());
+            Some(Proving {
+                executor: Arc::new(executor),
+                policy,
+                permits: Semaphore::new(args.max_concurrent_proofs),
+            })
+        }
+        None => None,
+    };
 
     let executor = Arc::new(
-        FullExecutor::<EthExecutorComponents<_>, _>::try_new(
-            http_provider.clone(),
-            elf,
-            block_execution_strategy_factory,
+        Executor::try_new(
+            args.http_rpc_url.clone(),
             prover_client,
-            PersistToPostgres::new(db_pool.clone()),
+            PersistToStore::new(store.clone(), worker_id.clone()),
             config,
         )
         .await?,
     );
 
-    // Subscribe to block headers.
-    let subscription = ws_provider.subscribe_blocks().await?;
-    let mut stream = subscription.into_stream().map(|h| h.number);
-
-    let concurrent_executions_semaphore = Arc::new(Semaphore::new(args.max_concurrent_executions));
-
-    while let Some(block_number) = stream.next().await {
-        info!("Received block: {:?}", block_number);
-
-        let executor = executor.clone();
-        let db_pool = db_pool.clone();
-        let alerting_client = alerting_client.clone();
-        let permit = concurrent_executions_semaphore.clone().acquire_owned().await?;
-
-        task::spawn(async move {
-            match process_block(block_number, executor, args.execution_retries).await {
-                Ok(_) => info!("Successfully processed block {}", block_number),
-                Err(err) => {
-                    let error_message = format!("Error executing block {}: {}", block_number, err);
-                    error!("{error_message}");
-
-                    if let Some(alerting_client) = &alerting_client {
-                        alerting_client
-                            .send_alert(format!("OP Succinct Explorer (RSP) - {error_message}"))
-                            .await;
-                    }
-
-                    if let Err(err) =
-                        db::update_block_status_as_failed(&db_pool, block_number).await
-                    {
-                        let error_message = format!(
-                            "Database error while updating block {} status: {}",
-                            block_number, err
-                        );
-
-                        error!("{error_message}",);
-
-                        if let Some(alerting_client) = &alerting_client {
-                            alerting_client
-                                .send_alert(format!("OP Succinct Explorer (RSP) - {error_message}"))
-                                .await;
-                        }
-                    }
+    if let (Some(from), Some(to)) = (args.enqueue_from, args.enqueue_to) {
+        let enqueued = store.enqueue_blocks(from, to).await?;
+        info!("Enqueued {enqueued} blocks between {from} and {to}");
+    }
+
+    // Spawn the workers executing the blocks from the queue.
+    let worker = Arc::new(Worker {
+        id: worker_id,
+        executor,
+        store: store.clone(),
+        alerting_client,
+        chain_id,
+        max_retries: args.execution_retries,
+        lease_duration: Duration::from_secs(args.lease_duration),
+        proving,
+    });
+
+    info!("Starting {} workers with ID {}", args.max_concurrent_executions, worker.id);
+
+    for _ in 0..args.max_concurrent_executions {
+        task::spawn(worker.clone().run());
+    }
+
+    let mut backoff = RESUBSCRIBE_MIN_BACKOFF;
+
+    loop {
+        match subscribe_and_enqueue(&args, &http_provider, &store).await {
+            Ok(received_blocks) => {
+                warn!("WebSocket subscription ended, resubscribing");
+
+                if received_blocks > 0 {
+                    backoff = RESUBSCRIBE_MIN_BACKOFF;
                 }
             }
+            Err(err) => error!("WebSocket subscription failed: {err}"),
+        }
 
-            drop(permit);
-        });
+        sleep(backoff).await;
+        backoff = (backoff * 2).min(RESUBSCRIBE_MAX_BACKOFF);
     }
-
-    Ok(())
 }
 
-#[instrument(skip(executor, max_retries))]
-async fn process_block<C, P>(
-    number: u64,
-    executor: Arc<FullExecutor<C, P>>,
-    max_retries: usize,
-) -> eyre::Result<()>
-where
-    C: ExecutorComponents<Network = Ethereum>,
-    P: Provider<Ethereum> + Clone,
-{
-    let mut retry_count = 0;
-
-    // Wait for the block to be avaliable in the HTTP provider
-    executor.wait_for_block(number).await?;
+/// Subscribes to the new block headers, enqueues the blocks missed since the last executed
+/// one, then enqueues the new blocks until the subscription ends.
+///
+/// Returns the number of blocks received from the subscription.
+async fn subscribe_and_enqueue(
+    args: &Args,
+    http_provider: &RootProvider<Ethereum>,
+    store: &Store,
+) -> eyre::Result<usize> {
+    let ws = WsConnect::new(args.ws_rpc_url.clone());
+    let ws_provider = ProviderBuilder::new().connect_ws(ws).await?;
+
+    // Subscribe to block headers.
+    let subscription = ws_provider.subscribe_blocks().await?;
+    let mut stream = subscription.into_stream();
 
-    loop {
-        match executor.execute(number).await {
-            Ok(_) => {
-                return Ok(());
-            }
-            Err(err) => {
-                warn!("Failed to execute block {number}: {err}, retrying...");
-                retry_count += 1;
-                if retry_count > max_retries {
-                    error!("Max retries reached for block: {number}");
-                    return Err(err);
-                }
+    // The blocks after the current head will be received from the subscription.
+    let head = http_provider.get_block_number().await?;
+
+    backfill(store, head, args.backfill_from, args.max_backfill_blocks).await?;
+
+    let mut received_blocks = 0;
+
+    while let Some(header) = stream.next().await {
+        info!("Received block: {:?}", header.number);
+        received_blocks += 1;
+
+        store.enqueue_block(header.number, Some(header.hash)).await?;
+    }
+
+    Ok(received_blocks)
+}
+
+/// Enqueues the blocks missing from the database up to `head`, and puts the stale ones back in
+/// the queue.
+///
+/// The backfill starts from the lowest block that is not executed, or the block following the
+/// last executed one, and at most `max_backfill_blocks` blocks before `head` are considered.
+async fn backfill(
+    store: &Store,
+    head: u64,
+    backfill_from: Option<u64>,
+    max_backfill_blocks: u64,
+) -> eyre::Result<()> {
+    let window_start = head.saturating_sub(max_backfill_blocks);
+    let from = match backfill_from {
+        Some(from) => from,
+        None => {
+            let after_last_executed = store.get_last_executed_block().await?.map(|n| n + 1);
+            let first_unexecuted = store.get_first_unexecuted_block(window_start).await?;
+
+            match after_last_executed.into_iter().chain(first_unexecuted).min() {
+                Some(from) => from,
+                // Nothing was executed nor queued yet, there is no gap to fill.
+                None => return Ok(()),
             }
         }
+    };
+    let from = from.max(window_start);
+
+    if from > head {
+        return Ok(());
     }
+
+    let requeued = store.requeue_stale_blocks(from, head).await?;
+    let enqueued = store.enqueue_blocks(from, head).await?;
+
+    if requeued > 0 {
+        info!("Requeuing {requeued} stale blocks between {from} and {head}");
+    }
+
+    if enqueued > 0 {
+        info!("Backfilling {enqueued} missing blocks between {from} and {head}");
+    }
+
+    Ok(())
 }
@@ -0,0 +1,90 @@
+use std::{fs, path::Path, sync::Arc};
+
+use alloy_primitives::{keccak256, B256};
+use eyre::eyre;
+use sp1_sdk::{SP1ProofWithPublicValues, SP1VerifyingKey};
+use tokio::sync::Semaphore;
+
+use crate::executor::Executor;
+
+/// Decides which executed blocks are also proven.
+#[derive(Debug, Clone, Copy)]
+pub enum ProvingPolicy {
+    /// Prove the blocks whose number is a multiple of the interval.
+    Every(u64),
+    /// Prove a fraction of the blocks, between 0 and 1.
+    ///
+    /// The sampling is derived from the block number, so a block is always either proven or not,
+    /// whatever the worker and the number of retries.
+    SampleRate(f64),
+}
+
+impl ProvingPolicy {
+    pub fn should_prove(&self, block_number: u64) -> bool {
+        match self {
+            ProvingPolicy::Every(interval) => block_number % interval == 0,
+            ProvingPolicy::SampleRate(rate) => {
+                let hash = keccak256(block_number.to_be_bytes());
+                let sample = u64::from_be_bytes(hash[..8].try_into().unwrap());
+
+                (sample as f64 / u64::MAX as f64) < *rate
+            }
+        }
+    }
+}
+
+/// Proves the blocks selected by the policy, with its own concurrency limit as proving is much
+/// more expensive than executing.
+pub struct Proving {
+    /// An executor configured to generate the proofs.
+    pub executor: Arc<Executor>,
+    pub policy: ProvingPolicy,
+    pub permits: Semaphore,
+}
+
+/// Writes the proof of a block to `{proof_dir}/{block_hash}.bin`, in the
+/// `SP1ProofWithPublicValues::save` format expected by `rsp-verify`.
+///
+/// The proof is named after the block hash, so the proof of a block reorged while it was being
+/// proven never replaces the proof of the canonical block.
+pub fn save_proof(
+    proof_dir: &Path,
+    block_hash: B256,
+    proof: &SP1ProofWithPublicValues,
+) -> eyre::Result<()> {
+    // Written aside then renamed into place, so that readers never see a partially written proof.
+    let tmp_path = proof_dir.join(format!("{block_hash}.bin.tmp"));
+    proof
+        .save(&tmp_path)
+        .map_err(|err| eyre!("Failed to save the proof of block {block_hash}: {err}"))?;
+    fs::rename(tmp_path, proof_dir.join(format!("{block_hash}.bin")))?;
+
+    Ok(())
+}
+
+/// Writes the verification key of the proofs to `{proof_dir}/vk.bin`.
+pub fn save_vk(proof_dir: &Path, vk: &SP1VerifyingKey) -> eyre::Result<()> {
+    fs::create_dir_all(proof_dir)?;
+    fs::write(proof_dir.join("vk.bin"), bincode::serialize(vk)?)?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_should_prove() {
+        let policy = ProvingPolicy::Every(10);
+        assert!(policy.should_prove(20));
+        assert!(!policy.should_prove(21));
+
+        assert!(!(0..100).any(|n| ProvingPolicy::SampleRate(0.0).should_prove(n)));
+        assert!((0..100).all(|n| ProvingPolicy::SampleRate(1.0).should_prove(n)));
+
+        let proven =
+            (0..10_000).filter(|n| ProvingPolicy::SampleRate(0.1).should_prove(*n)).count();
+        assert!((800..1200).contains(&proven));
+    }
+}
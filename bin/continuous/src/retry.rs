@@ -0,0 +1,147 @@
+use std::{fmt::Display, str::FromStr, time::Duration};
+
+use alloy_transport::TransportError;
+use rsp_host_executor::HostError;
+
+/// The delay before retrying a block after its first transient failure, doubled on each
+/// consecutive failure.
+const RETRY_MIN_BACKOFF: Duration = Duration::from_secs(5);
+
+/// The maximum delay before retrying a block after a transient failure.
+const RETRY_MAX_BACKOFF: Duration = Duration::from_secs(600);
+
+/// The class of a block execution error, deciding how the block is retried.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorClass {
+    /// The RPC or the database failed, the block is retried with an exponential backoff.
+    Transient,
+    /// The block execution itself failed and would fail again, the block is not retried.
+    Deterministic,
+    /// The host ran out of resources, the block is retried right away, possibly by another
+    /// worker.
+    Resource,
+}
+
+impl ErrorClass {
+    /// Classifies an error by looking for a known error in its chain.
+    ///
+    /// Unknown errors are considered transient, so they are retried without hammering the RPC.
+    pub fn classify(err: &eyre::Report) -> Self {
+        for cause in err.chain() {
+            if let Some(err) = cause.downcast_ref::<HostError>() {
+                return Self::from_host_error(err);
+            }
+
+            if cause.is::<TransportError>() {
+                return ErrorClass::Transient;
+            }
+
+            if let Some(err) = cause.downcast_ref::<sqlx::Error>() {
+                return match err {
+                    sqlx::Error::PoolTimedOut | sqlx::Error::PoolClosed => ErrorClass::Resource,
+                    _ => ErrorClass::Transient,
+                };
+            }
+
+            if cause.is::<std::io::Error>() {
+                return ErrorClass::Resource;
+            }
+        }
+
+        ErrorClass::Transient
+    }
+
+    fn from_host_error(err: &HostError) -> Self {
+        match err {
+            HostError::Transport(_) |
+            HostError::RpcDb(_) |
+            HostError::ExpectedBlock(_) |
+            HostError::ExpectedBlockId(_) |
+            HostError::RemoteProvingTimedOut(_, _) => ErrorClass::Transient,
+            HostError::ParseError(_) |
+            HostError::FailedToRecoverSenders |
+            HostError::PostExecutionCheck(_) |
+            HostError::ExecutionFailed(_) |
+            HostError::FromProof(_) |
+            HostError::HeaderMismatch(_, _) |
+            HostError::StateRootMismatch(_, _) |
+            HostError::StateUpdate(_) |
+            HostError::InvalidClientInput(_) |
+            HostError::IncompleteWitness(_) |
+            HostError::ClientExecutionFailed(_) |
+            HostError::ProofVerificationFailed(_) => ErrorClass::Deterministic,
+            HostError::FailedToReadGenesisFile(_) => ErrorClass::Resource,
+        }
+    }
+
+    /// Whether a block failing with this class of error should be retried.
+    pub fn is_retryable(&self) -> bool {
+        !matches!(self, ErrorClass::Deterministic)
+    }
+
+    /// Returns the delay before retrying a block that already failed `retry_count` times.
+    pub fn retry_delay(&self, retry_count: u32) -> Duration {
+        match self {
+            ErrorClass::Transient => RETRY_MIN_BACKOFF
+                .saturating_mul(2u32.saturating_pow(retry_count))
+                .min(RETRY_MAX_BACKOFF),
+            ErrorClass::Deterministic | ErrorClass::Resource => Duration::ZERO,
+        }
+    }
+}
+
+impl FromStr for ErrorClass {
+    type Err = eyre::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "transient" => Ok(ErrorClass::Transient),
+            "deterministic" => Ok(ErrorClass::Deterministic),
+            "resource" => Ok(ErrorClass::Resource),
+            _ => Err(eyre::eyre!("Unknown error class: {s}")),
+        }
+    }
+}
+
+impl Display for ErrorClass {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ErrorClass::Transient => write!(f, "transient"),
+            ErrorClass::Deterministic => write!(f, "deterministic"),
+            ErrorClass::Resource => write!(f, "resource"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy_primitives::B256;
+
+    use super::*;
+
+    #[test]
+    fn test_classify() {
+        let err = eyre::Report::new(HostError::StateRootMismatch(B256::ZERO, B256::ZERO));
+        assert_eq!(ErrorClass::classify(&err), ErrorClass::Deterministic);
+
+        let err = eyre::Report::new(HostError::ExpectedBlock(1)).wrap_err("Failed to execute");
+        assert_eq!(ErrorClass::classify(&err), ErrorClass::Transient);
+
+        let err =
+            eyre::Report::new(HostError::RemoteProvingTimedOut("42".to_string(), Duration::ZERO));
+        assert_eq!(ErrorClass::classify(&err), ErrorClass::Transient);
+
+        let err = eyre::Report::new(std::io::Error::other("No space left on device"));
+        assert_eq!(ErrorClass::classify(&err), ErrorClass::Resource);
+
+        assert_eq!(ErrorClass::classify(&eyre::eyre!("Unknown")), ErrorClass::Transient);
+    }
+
+    #[test]
+    fn test_retry_delay() {
+        assert_eq!(ErrorClass::Transient.retry_delay(0), RETRY_MIN_BACKOFF);
+        assert_eq!(ErrorClass::Transient.retry_delay(2), RETRY_MIN_BACKOFF * 4);
+        assert_eq!(ErrorClass::Transient.retry_delay(u32::MAX), RETRY_MAX_BACKOFF);
+        assert_eq!(ErrorClass::Resource.retry_delay(2), Duration::ZERO);
+    }
+}
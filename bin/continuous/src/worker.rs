@@ -0,0 +1,215 @@
+use std::{sync::Arc, time::Duration};
+
+use eyre::bail;
+use rsp_host_executor::alerting::{error_class_dedup_key, Alert, AlertSeverity, AlertingClient};
+use tokio::{task, time::sleep};
+use tracing::{error, info, instrument, warn};
+
+use crate::{
+    db::{BlockStore, Store},
+    executor::Executor,
+    proving::Proving,
+    retry::ErrorClass,
+};
+
+/// The error class used to deduplicate block execution alerts.
+const EXECUTION_ERROR_CLASS: &str = "execution";
+
+/// The delay before polling the queue again when it's empty.
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Every this many claims, the oldest block of the queue is claimed instead of the newest one,
+/// so the backfilled blocks keep being executed while the new heads are prioritized.
+const OLDEST_BLOCK_CLAIM_INTERVAL: u64 = 4;
+
+/// Pulls blocks from the `rsp_blocks` queue and executes them.
+pub struct Worker {
+    pub id: String,
+    pub executor: Arc<Executor>,
+    pub store: Arc<Store>,
+    pub alerting_client: Option<Arc<AlertingClient>>,
+    pub chain_id: u64,
+    pub max_retries: usize,
+    pub lease_duration: Duration,
+    /// Proves a sample of the blocks, if enabled.
+    pub proving: Option<Proving>,
+}
+
+impl Worker {
+    /// Executes the blocks from the queue, forever.
+    pub async fn run(self: Arc<Self>) {
+        let mut claims = 0u64;
+        loop {
+            claims = claims.wrapping_add(1);
+            let oldest_first = claims % OLDEST_BLOCK_CLAIM_INTERVAL == 0;
+            match self.claim_and_process(oldest_first).await {
+                Ok(true) => {}
+                Ok(false) => sleep(POLL_INTERVAL).await,
+                Err(err) => {
+                    error!("Worker {} failed to process the queue: {err}", self.id);
+                    sleep(POLL_INTERVAL).await;
+                }
+            }
+        }
+    }
+
+    /// Claims a block from the queue and executes it.
+    ///
+    /// Returns false if the queue is empty.
+    async fn claim_and_process(&self, oldest_first: bool) -> eyre::Result<bool> {
+        let Some(claimed_block) =
+            self.store.claim_block(&self.id, self.lease_duration, oldest_first).await?
+        else {
+            return Ok(false);
+        };
+        let block_number = claimed_block.block_number;
+
+        info!("Claimed block {block_number} (retry count: {})", claimed_block.retry_count);
+
+        // Keep the lease alive while the block is being executed.
+        let heartbeat = task::spawn(renew_lease(
+            self.store.clone(),
+            block_number,
+            self.id.clone(),
+            self.lease_duration,
+        ));
+        // The sampled blocks are executed by the proving executor, which also proves them.
+        let result = match self.proving.as_ref().filter(|p| p.policy.should_prove(block_number)) {
+            Some(proving) => {
+                let _permit =
+                    proving.permits.acquire().await.expect("the proving semaphore is never closed");
+                info!("Proving block {block_number}");
+
+                process_block(block_number, &self.id, &proving.executor, &self.store).await
+            }
+            None => process_block(block_number, &self.id, &self.executor, &self.store).await,
+        };
+        heartbeat.abort();
+
+        match result {
+            Ok(_) => {
+                info!("Successfully processed block {}", block_number);
+
+                if let Some(alerting_client) = &self.alerting_client {
+                    alerting_client
+                        .resolve(&error_class_dedup_key(self.chain_id, EXECUTION_ERROR_CLASS))
+                        .await;
+                }
+            }
+            Err(err) => {
+                let error_class = ErrorClass::classify(&err);
+                // Deterministic failures would fail again, so they are not retried.
+                let max_retries = if error_class.is_retryable() { self.max_retries } else { 0 };
+                let retry_delay = error_class.retry_delay(claimed_block.retry_count as u32);
+                // The retry count can't change while the block is claimed.
+                let exhausted = claimed_block.retry_count as usize >= max_retries;
+
+                let released = self
+                    .store
+                    .release_failed_block(
+                        block_number,
+                        &self.id,
+                        max_retries,
+                        error_class,
+                        retry_delay,
+                    )
+                    .await;
+
+                match released {
+                    Ok(true) if exhausted => {
+                        let error_message = format!(
+                            "Error executing block {} ({error_class}): {}",
+                            block_number, err
+                        );
+                        error!("{error_message}");
+
+                        if let Some(alerting_client) = &self.alerting_client {
+                            alerting_client
+                                .trigger(&Alert::for_error_class(
+                                    self.chain_id,
+                                    EXECUTION_ERROR_CLASS,
+                                    format!("OP Succinct Explorer (RSP) - {error_message}"),
+                                    AlertSeverity::Error,
+                                ))
+                                .await;
+                        }
+                    }
+                    Ok(true) => warn!(
+                        ?retry_delay,
+                        "Failed to execute block {block_number} ({error_class}): {err}, retrying..."
+                    ),
+                    Ok(false) => {
+                        warn!("Failed to execute block {block_number}: {err}, lease lost")
+                    }
+                    Err(db_err) => {
+                        let error_message = format!(
+                            "Database error while updating block {} status: {}",
+                            block_number, db_err
+                        );
+
+                        error!("{error_message}",);
+
+                        if let Some(alerting_client) = &self.alerting_client {
+                            alerting_client
+                                .trigger(&Alert::for_block(
+                                    self.chain_id,
+                                    block_number,
+                                    format!("OP Succinct Explorer (RSP) - {error_message}"),
+                                    AlertSeverity::Critical,
+                                ))
+                                .await;
+                        }
+                    }
+                }
+            }
+        }
+
+        Ok(true)
+    }
+}
+
+#[instrument(skip(executor, store))]
+async fn process_block(
+    number: u64,
+    worker_id: &str,
+    executor: &Executor,
+    store: &Store,
+) -> eyre::Result<()> {
+    // Wait for the block to be avaliable in the HTTP provider
+    executor.wait_for_block(number).await?;
+
+    let block_hash = executor.execute(number).await?;
+
+    // Make sure the executed block was not reorged out during the execution, otherwise put it
+    // back in the queue.
+    if !executor.is_canonical(number, block_hash).await? {
+        warn!(?block_hash, "Block {number} was reorged, requeuing...");
+
+        if !store.update_block_status_as_reorged(number, worker_id).await? {
+            bail!("Lost the lease on block {number}");
+        }
+    }
+
+    Ok(())
+}
+
+/// Renews the lease of the block periodically, until the task is aborted or the lease is lost.
+async fn renew_lease(
+    store: Arc<Store>,
+    block_number: u64,
+    worker_id: String,
+    lease_duration: Duration,
+) {
+    loop {
+        sleep(lease_duration / 3).await;
+
+        match store.renew_lease(block_number, &worker_id, lease_duration).await {
+            Ok(true) => {}
+            Ok(false) => {
+                warn!("Lost the lease on block {block_number}");
+                return;
+            }
+            Err(err) => error!("Failed to renew the lease on block {block_number}: {err}"),
+        }
+    }
+}
@@ -2,4 +2,5 @@ use sp1_build::build_program;
 
 fn main() {
     build_program("../client");
+    build_program("../client-op");
 }
@@ -1,7 +1,11 @@
+use std::path::PathBuf;
+
 use alloy_chains::Chain;
+use alloy_eips::BlockNumberOrTag;
+use alloy_provider::{Provider, RootProvider};
 use clap::Parser;
-use rsp_host_executor::Config;
-use rsp_primitives::genesis::Genesis;
+use rsp_host_executor::{genesis_from_path, parse_proof_mode, Config};
+use rsp_provider::create_provider;
 use sp1_sdk::SP1ProofMode;
 use url::Url;
 
@@ -16,14 +20,36 @@ pub struct Args {
     #[clap(long, env)]
     pub ws_rpc_url: Url,
 
+    /// The chain ID. Defaults to the chain ID of the HTTP RPC.
+    #[clap(long, env)]
+    pub chain_id: Option<u64>,
+
+    /// The path to the genesis json file of the chain, required for the chains not supported
+    /// natively.
+    #[clap(long, env)]
+    pub genesis_path: Option<PathBuf>,
+
     /// Whether to generate a proof or just execute the block.
     #[clap(long)]
     pub execute_only: bool,
 
+    /// The proof mode: core, compressed, groth16 or plonk.
+    #[clap(long, env, value_parser = parse_proof_mode, default_value = "compressed")]
+    pub proof_mode: SP1ProofMode,
+
+    /// Whether to verify the proofs before submitting them.
+    #[clap(long, env, conflicts_with = "execute_only")]
+    pub verify_proof: bool,
+
     /// The interval at which to execute blocks.
     #[clap(long, default_value_t = 100)]
     pub block_interval: u64,
 
+    /// Only execute blocks up to the block with this tag (e.g. `safe` or `finalized`), instead
+    /// of the new heads.
+    #[clap(long, env)]
+    pub block_tag: Option<BlockNumberOrTag>,
+
     /// ETH proofs endpoint.
     #[clap(long, env)]
     pub eth_proofs_endpoint: String,
@@ -40,22 +66,63 @@ pub struct Args {
     #[clap(long, env)]
     pub pager_duty_integration_key: Option<String>,
 
-    /// Moongate server endpoint.
+    /// Overrides the PagerDuty events endpoint.
     #[clap(long, env)]
+    pub pager_duty_endpoint: Option<Url>,
+
+    /// Slack or Discord compatible webhook URL, used when no PagerDuty key is set.
+    #[clap(long, env)]
+    pub alert_webhook_url: Option<Url>,
+
+    /// Log the alerts when no alerting backend is configured.
+    #[clap(long, env)]
+    pub log_alerts: bool,
+
+    /// Skip the pruning of the trie nodes that the client does not need from the client input.
+    #[clap(long, env)]
+    pub no_minimize_witness: bool,
+
+    /// Moongate server endpoint.
+    #[clap(long, env, conflicts_with = "remote_prover_url")]
     pub moongate_endpoint: Option<String>,
+
+    /// The endpoint of a remote proving service generating the proofs, instead of the local CUDA
+    /// prover.
+    #[clap(long, env)]
+    pub remote_prover_url: Option<Url>,
+
+    /// How long a remote proving job is waited for before giving up on it, in seconds.
+    #[clap(long, env, default_value_t = 3600)]
+    pub remote_prover_timeout: u64,
 }
 
 impl Args {
     pub async fn as_config(&self) -> eyre::Result<Config> {
+        let chain_id = match self.chain_id {
+            Some(chain_id) => chain_id,
+            None => {
+                let provider: RootProvider = create_provider(self.http_rpc_url.clone());
+                provider.get_chain_id().await?
+            }
+        };
+
+        let genesis = if let Some(genesis_path) = &self.genesis_path {
+            genesis_from_path(genesis_path)?
+        } else {
+            chain_id.try_into()?
+        };
+
         let config = Config {
-            chain: Chain::mainnet(),
-            genesis: Genesis::Mainnet,
+            chain: Chain::from_id(chain_id),
+            genesis,
             rpc_url: Some(self.http_rpc_url.clone()),
             cache_dir: None,
             custom_beneficiary: None,
-            prove_mode: (!self.execute_only).then_some(SP1ProofMode::Compressed),
+            prove_mode: (!self.execute_only).then_some(self.proof_mode),
             skip_client_execution: true,
             opcode_tracking: false,
+            verify_proof: self.verify_proof,
+            minimize_witness: !self.no_minimize_witness,
         };
 
         Ok(config)
@@ -1,29 +1,73 @@
-use std::time::Duration;
+use std::{
+    fmt::{self, Display},
+    time::Duration,
+};
 
+use alloy_primitives::B256;
+use alloy_provider::{Provider, RootProvider};
 use base64::{engine::general_purpose::STANDARD, Engine};
 use eyre::eyre;
 use reqwest_middleware::{ClientBuilder, ClientWithMiddleware};
 use reqwest_retry::{policies::ExponentialBackoff, RetryTransientMiddleware};
 use rsp_host_executor::ExecutionHooks;
-use sp1_sdk::{HashableKey, SP1VerifyingKey};
+use sp1_sdk::{HashableKey, SP1ProofWithPublicValues, SP1VerifyingKey};
 use tracing::error;
 
+/// The error returned when a proven block is not part of the canonical chain anymore, so its
+/// proof is not submitted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BlockReorged {
+    pub block_number: u64,
+    pub block_hash: B256,
+}
+
+impl Display for BlockReorged {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Block {} ({}) was reorged", self.block_number, self.block_hash)
+    }
+}
+
+impl std::error::Error for BlockReorged {}
+
 #[derive(Debug, Clone)]
 pub struct EthProofsClient {
     cluster_id: u64,
     endpoint: String,
     api_token: String,
     client: ClientWithMiddleware,
+    /// Used to check that the proven blocks are still canonical before submitting the proofs.
+    provider: RootProvider,
 }
 
 impl EthProofsClient {
-    pub fn new(cluster_id: u64, endpoint: String, api_token: String) -> Self {
+    pub fn new(
+        cluster_id: u64,
+        endpoint: String,
+        api_token: String,
+        provider: RootProvider,
+    ) -> Self {
         let retry_policy = ExponentialBackoff::builder().build_with_max_retries(3);
         let client = ClientBuilder::new(reqwest::Client::new())
             .with(RetryTransientMiddleware::new_with_policy(retry_policy))
             .build();
 
-        Self { cluster_id, endpoint, api_token, client }
+        Self { cluster_id, endpoint, api_token, client, provider }
+    }
+
+    /// Returns a [`BlockReorged`] error if the block with the given hash is not part of the
+    /// canonical chain anymore.
+    pub async fn ensure_canonical(&self, block_number: u64, block_hash: B256) -> eyre::Result<()> {
+        let block = self
+            .provider
+            .get_block_by_number(block_number.into())
+            .await?
+            .ok_or_else(|| eyre!("Block {block_number} not found"))?;
+
+        if block.header.hash != block_hash {
+            return Err(BlockReorged { block_number, block_hash }.into());
+        }
+
+        Ok(())
     }
 
     pub async fn queued(&self, block_number: u64) {
@@ -136,11 +180,16 @@ impl ExecutionHooks for EthProofsClient {
     async fn on_proving_end(
         &self,
         block_number: u64,
+        block_hash: B256,
+        _proof: &SP1ProofWithPublicValues,
         proof_bytes: &[u8],
         vk: &SP1VerifyingKey,
         cycle_count: Option<u64>,
         proving_duration: Duration,
     ) -> eyre::Result<()> {
+        // The block may have been reorged out while being proven.
+        self.ensure_canonical(block_number, block_hash).await?;
+
         self.proved(
             proof_bytes,
             block_number,
@@ -153,3 +202,37 @@ impl ExecutionHooks for EthProofsClient {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use alloy_rpc_client::RpcClient;
+    use alloy_transport::mock::Asserter;
+
+    use super::*;
+
+    fn client(asserter: Asserter) -> EthProofsClient {
+        EthProofsClient::new(
+            1,
+            "http://127.0.0.1:1".to_string(),
+            "token".to_string(),
+            RootProvider::new(RpcClient::mocked(asserter)),
+        )
+    }
+
+    #[tokio::test]
+    async fn test_reorged_block_is_not_submitted() {
+        let asserter = Asserter::new();
+        let canonical_block = <alloy_rpc_types::Block>::default();
+        asserter.push_success(&canonical_block);
+        asserter.push_success(&canonical_block);
+        let client = client(asserter);
+
+        client.ensure_canonical(1, canonical_block.header.hash).await.unwrap();
+
+        let err = client.ensure_canonical(1, B256::repeat_byte(1)).await.unwrap_err();
+        assert_eq!(
+            err.downcast_ref::<BlockReorged>(),
+            Some(&BlockReorged { block_number: 1, block_hash: B256::repeat_byte(1) })
+        );
+    }
+}
@@ -1,23 +1,34 @@
-use std::sync::Arc;
+use std::{sync::Arc, time::Duration};
 
-use alloy_provider::{Provider, ProviderBuilder, WsConnect};
+use alloy_primitives::B256;
+use alloy_provider::{Provider, ProviderBuilder, RootProvider, WsConnect};
 use clap::Parser;
 use cli::Args;
-use eth_proofs::EthProofsClient;
-use futures::{future::ready, StreamExt};
+use either::Either;
+use eth_proofs::{BlockReorged, EthProofsClient};
+use eyre::bail;
+use futures::StreamExt;
 use rsp_host_executor::{
-    alerting::AlertingClient, create_eth_block_execution_strategy_factory, BlockExecutor,
-    EthExecutorComponents, FullExecutor,
+    alerting::{error_class_dedup_key, Alert, AlertSeverity, AlertingClient},
+    create_eth_block_execution_strategy_factory, create_op_block_execution_strategy_factory,
+    remote_prover::RemoteProver,
+    BlockExecutor, EthExecutorComponents, ExecutorComponents, FullExecutor, OpExecutorComponents,
 };
 use rsp_provider::create_provider;
 use sp1_sdk::{include_elf, ProverClient};
-use tracing::{error, info};
+use tracing::{error, info, warn};
 use tracing_subscriber::{fmt, layer::SubscriberExt, util::SubscriberInitExt, EnvFilter};
 
 mod cli;
 
 mod eth_proofs;
 
+/// The maximum number of times a block is executed again after being reorged.
+const MAX_REORG_REEXECUTIONS: usize = 3;
+
+/// The error class used to deduplicate block execution alerts.
+const EXECUTION_ERROR_CLASS: &str = "execution";
+
 #[tokio::main]
 async fn main() -> eyre::Result<()> {
     // Initialize the environment variables.
@@ -42,60 +53,187 @@ async fn main() -> eyre::Result<()> {
     let args = Args::parse();
     let config = args.as_config().await?;
 
-    let elf = include_elf!("rsp-client").to_vec();
-    let block_execution_strategy_factory =
-        create_eth_block_execution_strategy_factory(&config.genesis, None);
-
     let eth_proofs_client = EthProofsClient::new(
         args.eth_proofs_cluster_id,
-        args.eth_proofs_endpoint,
-        args.eth_proofs_api_token,
+        args.eth_proofs_endpoint.clone(),
+        args.eth_proofs_api_token.clone(),
+        create_provider(args.http_rpc_url.clone()),
+    );
+    let alerting_client = AlertingClient::from_options(
+        args.pager_duty_integration_key.clone(),
+        args.pager_duty_endpoint.as_ref(),
+        args.alert_webhook_url.clone(),
+        args.log_alerts,
     );
-    let alerting_client = args.pager_duty_integration_key.map(AlertingClient::new);
-
-    let ws = WsConnect::new(args.ws_rpc_url);
-    let ws_provider = ProviderBuilder::new().connect_ws(ws).await?;
-    let http_provider = create_provider(args.http_rpc_url);
-
-    // Subscribe to block headers.
-    let subscription = ws_provider.subscribe_blocks().await?;
-    let mut stream =
-        subscription.into_stream().filter(|h| ready(h.number % args.block_interval == 0));
 
-    let builder = ProverClient::builder().cuda();
-    let client = if let Some(endpoint) = &args.moongate_endpoint {
-        builder.server(endpoint).build()
+    let client = if let Some(url) = &args.remote_prover_url {
+        Either::Right(
+            RemoteProver::new(url.clone(), ProverClient::builder().cpu().build())
+                .with_timeout(Duration::from_secs(args.remote_prover_timeout)),
+        )
     } else {
-        builder.build()
+        let builder = ProverClient::builder().cuda();
+        let client = if let Some(endpoint) = &args.moongate_endpoint {
+            builder.server(endpoint).build()
+        } else {
+            builder.build()
+        };
+
+        Either::Left(client)
     };
 
     let client = Arc::new(client);
 
-    let executor = FullExecutor::<EthExecutorComponents<_, _>, _>::try_new(
-        http_provider.clone(),
-        elf,
-        block_execution_strategy_factory,
-        client,
-        eth_proofs_client,
-        config,
-    )
-    .await?;
+    info!("Proving blocks of chain {}", config.chain);
 
-    info!("Latest block number: {}", http_provider.get_block_number().await?);
+    if config.chain.is_optimism() {
+        let elf = include_elf!("rsp-client-op").to_vec();
+        let block_execution_strategy_factory =
+            create_op_block_execution_strategy_factory(&config.genesis);
 
-    while let Some(header) = stream.next().await {
-        // Wait for the block to be avaliable in the HTTP provider
-        executor.wait_for_block(header.number).await?;
+        let executor = FullExecutor::<OpExecutorComponents<_, _>, _>::try_new(
+            create_provider(args.http_rpc_url.clone()),
+            elf,
+            block_execution_strategy_factory,
+            client,
+            eth_proofs_client,
+            config,
+        )
+        .await?;
 
-        if let Err(err) = executor.execute(header.number).await {
-            let error_message = format!("Error handling block {}: {err}", header.number);
-            error!(error_message);
+        run(&args, executor, alerting_client).await
+    } else {
+        let elf = include_elf!("rsp-client").to_vec();
+        let block_execution_strategy_factory =
+            create_eth_block_execution_strategy_factory(&config.genesis, None);
+
+        let executor = FullExecutor::<EthExecutorComponents<_, _>, _>::try_new(
+            create_provider(args.http_rpc_url.clone()),
+            elf,
+            block_execution_strategy_factory,
+            client,
+            eth_proofs_client,
+            config,
+        )
+        .await?;
 
-            if let Some(alerting_client) = &alerting_client {
-                alerting_client.send_alert(error_message).await;
+        run(&args, executor, alerting_client).await
+    }
+}
+
+/// Executes and proves the new blocks, forever.
+async fn run<C>(
+    args: &Args,
+    executor: FullExecutor<C, RootProvider<C::Network>>,
+    alerting_client: Option<AlertingClient>,
+) -> eyre::Result<()>
+where
+    C: ExecutorComponents,
+{
+    let chain_id = executor.config().chain.id();
+
+    let ws = WsConnect::new(args.ws_rpc_url.clone());
+    let ws_provider = ProviderBuilder::new().connect_ws(ws).await?;
+    let http_provider: RootProvider = create_provider(args.http_rpc_url.clone());
+
+    // Subscribe to block headers.
+    let subscription = ws_provider.subscribe_blocks().await?;
+    let mut stream = subscription.into_stream();
+
+    info!("Latest block number: {}", http_provider.get_block_number().await?);
+
+    // The last tagged block seen, used to process every block between two tag updates.
+    let mut last_tagged_block: Option<u64> = None;
+
+    while let Some(header) = stream.next().await {
+        let block_numbers = match args.block_tag {
+            None => vec![header.number],
+            Some(tag) => match http_provider.get_block_by_number(tag).await {
+                Ok(Some(block)) => {
+                    let tagged_block = block.header.number;
+                    let from = last_tagged_block.map_or(tagged_block, |last| last + 1);
+                    last_tagged_block = Some(tagged_block);
+
+                    (from..=tagged_block).collect()
+                }
+                Ok(None) => {
+                    warn!("No {tag} block found");
+                    continue;
+                }
+                Err(err) => {
+                    warn!("Failed to fetch the {tag} block: {err}");
+                    continue;
+                }
+            },
+        };
+
+        for block_number in block_numbers.into_iter().filter(|n| n % args.block_interval == 0) {
+            // Wait for the block to be avaliable in the HTTP provider
+            executor.wait_for_block(block_number).await?;
+
+            match execute_canonical(&executor, block_number).await {
+                Ok(_) => {
+                    if let Some(alerting_client) = &alerting_client {
+                        alerting_client
+                            .resolve(&error_class_dedup_key(chain_id, EXECUTION_ERROR_CLASS))
+                            .await;
+                    }
+                }
+                Err(err) => {
+                    let error_message = format!("Error handling block {}: {err}", block_number);
+                    error!(error_message);
+
+                    if let Some(alerting_client) = &alerting_client {
+                        alerting_client
+                            .trigger(&Alert::for_error_class(
+                                chain_id,
+                                EXECUTION_ERROR_CLASS,
+                                error_message,
+                                AlertSeverity::Error,
+                            ))
+                            .await;
+                    }
+                }
             }
         }
     }
 
     Ok(())
 }
+
+/// Executes the block, and executes it again if it was reorged out in the meantime.
+///
+/// The proofs of the blocks reorged out while being proven are not submitted, see
+/// [`EthProofsClient::ensure_canonical`].
+async fn execute_canonical<C, P>(
+    executor: &FullExecutor<C, P>,
+    block_number: u64,
+) -> eyre::Result<B256>
+where
+    C: ExecutorComponents,
+    P: Provider<C::Network> + Clone,
+{
+    for _ in 0..=MAX_REORG_REEXECUTIONS {
+        let block_hash = match executor.execute(block_number.into()).await {
+            Ok(block_hash) => block_hash,
+            Err(err) => match err.downcast_ref::<BlockReorged>() {
+                Some(reorged) => {
+                    warn!(
+                        block_hash = ?reorged.block_hash,
+                        "Block {block_number} was reorged while being proven, re-executing..."
+                    );
+                    continue;
+                }
+                None => return Err(err),
+            },
+        };
+
+        if executor.is_canonical(block_number, block_hash).await? {
+            return Ok(block_hash);
+        }
+
+        warn!(?block_hash, "Block {block_number} was reorged, re-executing...");
+    }
+
+    bail!("Block {block_number} kept being reorged")
+}
@@ -0,0 +1,115 @@
+use std::{fs, path::PathBuf};
+
+use alloy_primitives::B256;
+use alloy_provider::{network::AnyNetwork, Provider, RootProvider};
+use clap::{ArgGroup, Parser};
+use eyre::{bail, eyre};
+use rsp_client_executor::io::CommittedHeader;
+use sp1_sdk::{include_elf, HashableKey, Prover, ProverClient, SP1ProofWithPublicValues};
+use url::Url;
+
+/// The arguments for the verify executable.
+#[derive(Debug, Clone, Parser)]
+#[clap(group(
+    ArgGroup::new("block").required(true).multiple(true).args(["block_number", "block_hash"])
+))]
+struct VerifyArgs {
+    /// The path to the proof, as saved by `SP1ProofWithPublicValues::save`.
+    #[clap(long)]
+    proof_path: PathBuf,
+
+    /// The path to the client ELF the proof was generated with. Defaults to the ELF of the
+    /// Ethereum client.
+    #[clap(long)]
+    elf_path: Option<PathBuf>,
+
+    /// Whether the proof was generated with the OP Stack client.
+    #[clap(long, conflicts_with = "elf_path")]
+    op: bool,
+
+    /// The expected hash of the verifying key.
+    #[clap(long)]
+    vk_hash: Option<B256>,
+
+    /// The expected number of the proven block.
+    #[clap(long)]
+    block_number: Option<u64>,
+
+    /// The expected hash of the proven block.
+    #[clap(long)]
+    block_hash: Option<B256>,
+
+    /// The rpc url used to check that the proven block is canonical.
+    #[clap(long)]
+    rpc_url: Option<Url>,
+}
+
+#[tokio::main]
+async fn main() -> eyre::Result<()> {
+    // Initialize the environment variables.
+    dotenv::dotenv().ok();
+
+    let args = VerifyArgs::parse();
+
+    let elf = match &args.elf_path {
+        Some(elf_path) => fs::read(elf_path)?,
+        None if args.op => include_elf!("rsp-client-op").to_vec(),
+        None => include_elf!("rsp-client").to_vec(),
+    };
+
+    let client = ProverClient::builder().cpu().build();
+    let (_, vk) = client.setup(&elf);
+    let vk_hash = B256::from(vk.bytes32_raw());
+
+    if let Some(expected_vk_hash) = args.vk_hash {
+        if vk_hash != expected_vk_hash {
+            bail!("Verifying key mismatch \n found {vk_hash} expected {expected_vk_hash}");
+        }
+    }
+
+    let proof = SP1ProofWithPublicValues::load(&args.proof_path)
+        .map_err(|err| eyre!("Failed to load the proof: {err}"))?;
+
+    client.verify(&proof, &vk).map_err(|err| eyre!("Proof verification failed: {err}"))?;
+
+    // Read the block header committed by the client.
+    let header = proof.public_values.clone().read::<CommittedHeader>().header;
+    let block_hash = header.hash_slow();
+
+    if let Some(expected_block_number) = args.block_number {
+        if header.number != expected_block_number {
+            bail!(
+                "Block number mismatch \n found {} expected {expected_block_number}",
+                header.number
+            );
+        }
+    }
+
+    if let Some(expected_block_hash) = args.block_hash {
+        if block_hash != expected_block_hash {
+            bail!("Header mismatch \n found {block_hash} expected {expected_block_hash}");
+        }
+    }
+
+    if let Some(rpc_url) = args.rpc_url {
+        let provider = RootProvider::<AnyNetwork>::new_http(rpc_url);
+        let block = provider
+            .get_block_by_number(header.number.into())
+            .await?
+            .ok_or_else(|| eyre!("RPC didnt have expected block height {}", header.number))?;
+
+        if block.header.hash != block_hash {
+            bail!(
+                "Header mismatch with the RPC \n found {block_hash} expected {}",
+                block.header.hash
+            );
+        }
+    }
+
+    println!(
+        "Proof verified for block {} ({block_hash}), with verifying key {vk_hash}",
+        header.number
+    );
+
+    Ok(())
+}
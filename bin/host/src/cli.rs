@@ -1,20 +1,31 @@
-use std::{fs, path::PathBuf};
+use std::path::PathBuf;
 
 use alloy_chains::Chain;
-use alloy_primitives::Address;
+use alloy_eips::{BlockId, BlockNumberOrTag};
+use alloy_primitives::{Address, B256};
 use alloy_provider::{network::AnyNetwork, Provider, RootProvider};
-use clap::Parser;
-use rsp_host_executor::Config;
-use rsp_primitives::genesis::Genesis;
+use clap::{ArgGroup, Parser};
+use rsp_host_executor::{genesis_from_path, parse_proof_mode, Config};
 use sp1_sdk::SP1ProofMode;
 use url::Url;
 
 /// The arguments for the host executable.
 #[derive(Debug, Clone, Parser)]
+#[clap(group(
+    ArgGroup::new("block").required(true).args(["block_number", "block_hash", "block_tag"])
+))]
 pub struct HostArgs {
     /// The block number of the block to execute.
     #[clap(long)]
-    pub block_number: u64,
+    pub block_number: Option<u64>,
+
+    /// The hash of the block to execute.
+    #[clap(long)]
+    pub block_hash: Option<B256>,
+
+    /// The tag of the block to execute (e.g. `latest`, `safe` or `finalized`).
+    #[clap(long)]
+    pub block_tag: Option<BlockNumberOrTag>,
 
     #[clap(flatten)]
     pub provider: ProviderArgs,
@@ -31,6 +42,27 @@ pub struct HostArgs {
     #[clap(long)]
     pub prove: bool,
 
+    /// The proof mode: core, compressed, groth16 or plonk.
+    #[clap(long, value_parser = parse_proof_mode, default_value = "compressed")]
+    pub proof_mode: SP1ProofMode,
+
+    /// Whether to verify the proof once generated.
+    #[clap(long, requires = "prove")]
+    pub verify_proof: bool,
+
+    /// The endpoint of a remote proving service generating the proofs, instead of the local
+    /// prover.
+    #[clap(long, env, requires = "prove")]
+    pub remote_prover_url: Option<Url>,
+
+    /// How long a remote proving job is waited for before giving up on it, in seconds.
+    #[clap(long, env, default_value_t = 3600)]
+    pub remote_prover_timeout: u64,
+
+    /// Skip the pruning of the trie nodes that the client does not need from the client input.
+    #[clap(long)]
+    pub no_minimize_witness: bool,
+
     /// Optional path to the directory containing cached client input. A new cache file will be
     /// created from RPC data if it doesn't already exist.
     #[clap(long)]
@@ -49,6 +81,16 @@ pub struct HostArgs {
 }
 
 impl HostArgs {
+    /// Returns the id of the block to execute.
+    pub fn block_id(&self) -> BlockId {
+        match (self.block_number, self.block_hash, self.block_tag) {
+            (Some(block_number), _, _) => block_number.into(),
+            (_, Some(block_hash), _) => block_hash.into(),
+            (_, _, Some(block_tag)) => block_tag.into(),
+            _ => unreachable!("one of the block arguments is required"),
+        }
+    }
+
     pub async fn as_config(&self) -> eyre::Result<Config> {
         // We don't need RPC when using cache with known chain ID, so we leave it as `Option<Url>`
         // here and decide on whether to panic later.
@@ -80,11 +122,7 @@ impl HostArgs {
         };
 
         let genesis = if let Some(genesis_path) = &self.genesis_path {
-            let genesis_json = fs::read_to_string(genesis_path)
-                .map_err(|err| eyre::eyre!("Failed to read genesis file: {err}"))?;
-            let genesis = serde_json::from_str::<alloy_genesis::Genesis>(&genesis_json)?;
-
-            Genesis::Custom(genesis.config)
+            genesis_from_path(genesis_path)?
         } else {
             chain_id.try_into()?
         };
@@ -97,9 +135,11 @@ impl HostArgs {
             rpc_url,
             cache_dir: self.cache_dir.clone(),
             custom_beneficiary: self.custom_beneficiary,
-            prove_mode: self.prove.then_some(SP1ProofMode::Compressed),
+            prove_mode: self.prove.then_some(self.proof_mode),
             skip_client_execution: false,
             opcode_tracking: self.opcode_tracking,
+            verify_proof: self.verify_proof,
+            minimize_witness: !self.no_minimize_witness,
         };
 
         Ok(config)
@@ -33,6 +33,7 @@ const PRECOMPILES: [&str; 10] = [
 struct ExecutionReportData {
     chain_id: u64,
     block_number: u64,
+    block_hash: String,
     gas_used: u64,
     tx_count: usize,
     number_cycles: u64,
@@ -67,6 +68,7 @@ impl PersistExecutionReport {
         let mut headers = vec![
             "chain_id".to_string(),
             "block_number".to_string(),
+            "block_hash".to_string(),
             "gas_used".to_string(),
             "tx_count".to_string(),
         ];
@@ -123,6 +125,7 @@ impl PersistExecutionReport {
         let mut record = vec![
             self.chain_id.to_string(),
             block.number.to_string(),
+            block.header.hash_slow().to_string(),
             block.header.gas_used().to_string(),
             block.body.transaction_count().to_string(),
         ];
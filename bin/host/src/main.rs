@@ -1,16 +1,17 @@
 #![cfg_attr(not(test), warn(unused_crate_dependencies))]
 
-use std::sync::Arc;
+use std::{sync::Arc, time::Duration};
 
 use clap::Parser;
+use either::Either;
 use execute::PersistExecutionReport;
 use rsp_host_executor::{
     build_executor, create_eth_block_execution_strategy_factory,
-    create_op_block_execution_strategy_factory, BlockExecutor, EthExecutorComponents,
-    OpExecutorComponents,
+    create_op_block_execution_strategy_factory, remote_prover::RemoteProver, BlockExecutor,
+    EthExecutorComponents, OpExecutorComponents,
 };
 use rsp_provider::create_provider;
-use sp1_sdk::{include_elf, EnvProver};
+use sp1_sdk::{include_elf, EnvProver, ProverClient};
 use tracing_subscriber::{
     filter::EnvFilter, fmt, prelude::__tracing_subscriber_SubscriberExt, util::SubscriberInitExt,
 };
@@ -42,7 +43,7 @@ async fn main() -> eyre::Result<()> {
 
     // Parse the command line arguments.
     let args = HostArgs::parse();
-    let block_number = args.block_number;
+    let block_id = args.block_id();
     let report_path = args.report_path.clone();
     let config = args.as_config().await?;
     let persist_execution_report = PersistExecutionReport::new(
@@ -52,7 +53,13 @@ async fn main() -> eyre::Result<()> {
         args.opcode_tracking,
     );
 
-    let prover_client = Arc::new(EnvProver::new());
+    let prover_client = Arc::new(match &args.remote_prover_url {
+        Some(url) => Either::Right(
+            RemoteProver::new(url.clone(), ProverClient::builder().cpu().build())
+                .with_timeout(Duration::from_secs(args.remote_prover_timeout)),
+        ),
+        None => Either::Left(EnvProver::new()),
+    });
 
     if config.chain.is_optimism() {
         let elf = include_elf!("rsp-client-op").to_vec();
@@ -60,7 +67,7 @@ async fn main() -> eyre::Result<()> {
             create_op_block_execution_strategy_factory(&config.genesis);
         let provider = config.rpc_url.as_ref().map(|url| create_provider(url.clone()));
 
-        let executor = build_executor::<OpExecutorComponents<_>, _>(
+        let executor = build_executor::<OpExecutorComponents<_, _>, _>(
             elf,
             provider,
             block_execution_strategy_factory,
@@ -70,14 +77,14 @@ async fn main() -> eyre::Result<()> {
         )
         .await?;
 
-        executor.execute(block_number).await?;
+        executor.execute(block_id).await?;
     } else {
         let elf = include_elf!("rsp-client").to_vec();
         let block_execution_strategy_factory =
             create_eth_block_execution_strategy_factory(&config.genesis, config.custom_beneficiary);
         let provider = config.rpc_url.as_ref().map(|url| create_provider(url.clone()));
 
-        let executor = build_executor::<EthExecutorComponents<_>, _>(
+        let executor = build_executor::<EthExecutorComponents<_, _>, _>(
             elf,
             provider,
             block_execution_strategy_factory,
@@ -87,7 +94,7 @@ async fn main() -> eyre::Result<()> {
         )
         .await?;
 
-        executor.execute(block_number).await?;
+        executor.execute(block_id).await?;
     }
 
     Ok(())
@@ -36,6 +36,8 @@ async fn test_in_zkvm() {
         prove_mode: None,
         skip_client_execution: false,
         opcode_tracking: false,
+        verify_proof: false,
+        minimize_witness: true,
     };
 
     let rpc_url = Url::parse(env::var("RPC_1").unwrap().as_str()).expect("invalid rpc url");
@@ -57,7 +59,7 @@ async fn test_in_zkvm() {
     .await
     .unwrap();
 
-    executor.execute(20600000).await.unwrap();
+    executor.execute(20600000u64.into()).await.unwrap();
 }
 
 enum Hook {
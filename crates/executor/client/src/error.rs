@@ -1,7 +1,7 @@
-use alloy_primitives::{Address, FixedBytes};
+use alloy_primitives::{Address, FixedBytes, B256, U256};
 use reth_consensus::ConsensusError;
 use reth_evm::execute::BlockExecutionError;
-use rsp_mpt::Error as MptError;
+use rsp_mpt::{Error as MptError, StateError};
 
 #[derive(Debug, thiserror::Error)]
 pub enum ClientError {
@@ -15,8 +15,22 @@ pub enum ClientError {
     UnknownChainId(u64),
     #[error("Missing bytecode for account {}", .0)]
     MissingBytecode(Address),
+    #[error("Bytecode {} must be provided", .0)]
+    MissingBytecodeByHash(B256),
+    #[error("Block hash {} must be provided", .0)]
+    MissingBlockHash(u64),
     #[error("Missing trie for address {}", .0)]
     MissingTrie(Address),
+    #[error("Account {} cannot be resolved from the state trie: {}", .0, .1)]
+    UnresolvedAccount(Address, MptError),
+    #[error("Slot {} of address {} cannot be resolved from its storage trie: {}", .1, .0, .2)]
+    UnresolvedStorage(Address, U256, MptError),
+    #[error("Failed to update the state: {}", .0)]
+    StateUpdate(#[from] StateError),
+    #[error("The parent header must be provided")]
+    MissingParentHeader,
+    #[error("Invalid block: {}", .0)]
+    InvalidBlock(ConsensusError),
     #[error("Invalid block number found in headers \n expected: {} found: {}", .0, .1)]
     InvalidHeaderBlockNumber(u64, u64),
     #[error("Invalid parent header found for block \n expected: {}, found: {}", .0, .1)]
@@ -9,7 +9,7 @@ use reth_evm::{
     ConfigureEvm, OnStateHook,
 };
 use reth_evm_ethereum::EthEvmConfig;
-use reth_execution_types::ExecutionOutcome;
+use reth_execution_types::{BlockExecutionOutput, ExecutionOutcome};
 use reth_primitives_traits::Block;
 use reth_trie::KeccakKeyHasher;
 use revm::database::WrapDatabaseRef;
@@ -58,11 +58,11 @@ where
 
         // Initialize the witnessed database with verified storage proofs.
         let db = profile_report!(INIT_WITNESS_DB, {
-            let trie_db = input.witness_db(&sealed_headers).unwrap();
-            WrapDatabaseRef(trie_db)
-        });
+            input.witness_db(&sealed_headers).map(WrapDatabaseRef)
+        })?;
 
-        let block_executor = BlockExecutor::new(self.evm_config.clone(), db, input.opcode_tracking);
+        let mut block_executor =
+            BlockExecutor::new(self.evm_config.clone(), db, input.opcode_tracking);
 
         let block = profile_report!(RECOVER_SENDERS, {
             C::Primitives::from_input_block(input.current_block.clone())
@@ -73,23 +73,34 @@ where
         // Validate the blocks.
         profile_report!(VALIDATE_HEADER, {
             C::Primitives::validate_block(&block, self.chain_spec.clone())
-                .expect("The block is invalid");
+                .map_err(ClientError::InvalidBlock)?;
 
             for (header, parent) in sealed_headers.iter().tuple_windows() {
                 C::Primitives::validate_header(parent, self.chain_spec.clone())
-                    .expect("A parent header is invalid");
+                    .map_err(ClientError::InvalidBlock)?;
 
                 C::Primitives::validate_header_against_parent(
                     header,
                     parent,
                     self.chain_spec.clone(),
                 )
-                .expect("The header is invalid against its parent");
+                .map_err(ClientError::InvalidBlock)?;
             }
-        });
 
-        let execution_output =
-            profile_report!(BLOCK_EXECUTION, { block_executor.execute(&block) })?;
+            Ok::<_, ClientError>(())
+        })?;
+
+        let execution_output = profile_report!(BLOCK_EXECUTION, {
+            let result = block_executor.execute_one(&block);
+            let mut state = block_executor.into_state();
+
+            // a read of data missing from the state fails with an opaque database error, so
+            // report the missing data instead
+            match state.database.0.take_error() {
+                Some(err) => Err(err),
+                None => Ok(BlockExecutionOutput { result: result?, state: state.take_bundle() }),
+            }
+        })?;
 
         // Validate the block post execution.
         profile_report!(VALIDATE_EXECUTION, {
@@ -110,9 +121,9 @@ where
 
         // Verify the state root.
         let state_root = profile_report!(COMPUTE_STATE_ROOT, {
-            input.parent_state.update(&executor_outcome.hash_state_slow::<KeccakKeyHasher>());
-            input.parent_state.state_root()
-        });
+            input.parent_state.update(&executor_outcome.hash_state_slow::<KeccakKeyHasher>())?;
+            Ok::<_, ClientError>(input.parent_state.state_root())
+        })?;
 
         if state_root != input.current_block.header().state_root() {
             return Err(ClientError::MismatchedStateRoot);
@@ -254,3 +265,43 @@ where
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use alloy_consensus::BlockBody;
+    use alloy_primitives::{map::HashMap, B256};
+    use reth_chainspec::MAINNET;
+    use rsp_mpt::{EthereumState, MptNode};
+    use rsp_primitives::genesis::Genesis;
+
+    use super::*;
+    use crate::io::EthClientExecutorInput;
+
+    #[test]
+    fn test_invalid_witness_is_rejected() {
+        // the parent state doesn't match the state root of the parent header
+        let parent_header = Header { state_root: B256::repeat_byte(1), ..Default::default() };
+        let input = EthClientExecutorInput {
+            current_block: reth_ethereum_primitives::Block {
+                header: Header {
+                    number: 1,
+                    parent_hash: parent_header.hash_slow(),
+                    ..Default::default()
+                },
+                body: BlockBody::default(),
+            },
+            ancestor_headers: vec![parent_header],
+            parent_state: EthereumState {
+                state_trie: MptNode::default(),
+                storage_tries: HashMap::default(),
+            },
+            bytecodes: vec![],
+            genesis: Genesis::Mainnet,
+            custom_beneficiary: None,
+            opcode_tracking: false,
+        };
+
+        let err = EthClientExecutor::eth(MAINNET.clone(), None).execute(input).unwrap_err();
+        assert!(matches!(err, ClientError::MismatchedStateRoot));
+    }
+}
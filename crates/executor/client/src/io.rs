@@ -1,4 +1,4 @@
-use std::iter::once;
+use std::{cell::RefCell, iter::once};
 
 use alloy_consensus::{Block, BlockHeader, Header};
 use alloy_primitives::map::HashMap;
@@ -12,7 +12,7 @@ use revm::{
     DatabaseRef,
 };
 use revm_primitives::{keccak256, Address, B256, U256};
-use rsp_mpt::EthereumState;
+use rsp_mpt::{AccountStorage, EthereumState};
 use rsp_primitives::genesis::Genesis;
 use serde::{Deserialize, Serialize};
 use serde_with::serde_as;
@@ -41,7 +41,8 @@ pub struct ClientExecutorInput<P: NodePrimitives> {
     /// to provide the parent state root.
     #[serde_as(as = "Vec<alloy_consensus::serde_bincode_compat::Header>")]
     pub ancestor_headers: Vec<Header>,
-    /// Network state as of the parent block.
+    /// Network state as of the parent block, serialized as a flat list of trie nodes.
+    #[serde(with = "rsp_mpt::flat_witness")]
     pub parent_state: EthereumState,
     /// Account bytecodes.
     pub bytecodes: Vec<Bytecode>,
@@ -62,6 +63,11 @@ impl<P: NodePrimitives> ClientExecutorInput<P> {
 
     /// Creates a [`WitnessDb`].
     pub fn witness_db(&self, sealed_headers: &[SealedHeader]) -> Result<TrieDB<'_>, ClientError> {
+        // the parent header provides the state root the state is checked against
+        if self.ancestor_headers.is_empty() {
+            return Err(ClientError::MissingParentHeader);
+        }
+
         <Self as WitnessInput>::witness_db(self, sealed_headers)
     }
 }
@@ -108,6 +114,9 @@ pub struct TrieDB<'a> {
     inner: &'a EthereumState,
     block_hashes: HashMap<u64, B256>,
     bytecode_by_hash: HashMap<B256, &'a Bytecode>,
+    /// The first error caused by data missing from the state, which the EVM only reports as a
+    /// database error.
+    error: RefCell<Option<ClientError>>,
 }
 
 impl<'a> TrieDB<'a> {
@@ -116,7 +125,20 @@ impl<'a> TrieDB<'a> {
         block_hashes: HashMap<u64, B256>,
         bytecode_by_hash: HashMap<B256, &'a Bytecode>,
     ) -> Self {
-        Self { inner, block_hashes, bytecode_by_hash }
+        Self { inner, block_hashes, bytecode_by_hash, error: RefCell::new(None) }
+    }
+
+    /// Takes the first error caused by data missing from the state.
+    pub fn take_error(&self) -> Option<ClientError> {
+        self.error.take()
+    }
+
+    /// Records the error, unless an earlier one was recorded, and converts it to a database
+    /// error.
+    fn fail(&self, err: ClientError) -> ProviderError {
+        let message = err.to_string();
+        self.error.borrow_mut().get_or_insert(err);
+        ProviderError::TrieWitnessError(message)
     }
 }
 
@@ -129,7 +151,11 @@ impl DatabaseRef for TrieDB<'_> {
         let hashed_address = keccak256(address);
         let hashed_address = hashed_address.as_slice();
 
-        let account_in_trie = self.inner.state_trie.get_rlp::<TrieAccount>(hashed_address).unwrap();
+        let account_in_trie = self
+            .inner
+            .state_trie
+            .get_rlp::<TrieAccount>(hashed_address)
+            .map_err(|err| self.fail(ClientError::UnresolvedAccount(address, err)))?;
 
         let account = account_in_trie.map(|account_in_trie| AccountInfo {
             balance: account_in_trie.balance,
@@ -143,32 +169,40 @@ impl DatabaseRef for TrieDB<'_> {
 
     /// Get account code by its hash.
     fn code_by_hash_ref(&self, hash: B256) -> Result<Bytecode, Self::Error> {
-        Ok(self.bytecode_by_hash.get(&hash).map(|code| (*code).clone()).unwrap())
+        self.bytecode_by_hash
+            .get(&hash)
+            .map(|code| (*code).clone())
+            .ok_or_else(|| self.fail(ClientError::MissingBytecodeByHash(hash)))
     }
 
     /// Get storage value of address at index.
     fn storage_ref(&self, address: Address, index: U256) -> Result<U256, Self::Error> {
         let hashed_address = keccak256(address);
-        let hashed_address = hashed_address.as_slice();
 
-        let storage_trie = self
+        let storage = self
             .inner
-            .storage_tries
-            .get(hashed_address)
-            .expect("A storage trie must be provided for each account");
+            .storage(&hashed_address)
+            .map_err(|err| self.fail(ClientError::UnresolvedAccount(address, err)))?;
+        let storage_trie = match storage {
+            AccountStorage::Empty => return Ok(U256::ZERO),
+            AccountStorage::Provided(storage_trie) => storage_trie,
+            AccountStorage::NotProvided(_) => {
+                return Err(self.fail(ClientError::MissingTrie(address)))
+            }
+        };
 
         Ok(storage_trie
             .get_rlp::<U256>(keccak256(index.to_be_bytes::<32>()).as_slice())
-            .expect("Can get from MPT")
+            .map_err(|err| self.fail(ClientError::UnresolvedStorage(address, index, err)))?
             .unwrap_or_default())
     }
 
     /// Get block hash by block number.
     fn block_hash_ref(&self, number: u64) -> Result<B256, Self::Error> {
-        Ok(*self
-            .block_hashes
+        self.block_hashes
             .get(&number)
-            .expect("A block hash must be provided for each block number"))
+            .copied()
+            .ok_or_else(|| self.fail(ClientError::MissingBlockHash(number)))
     }
 }
 
@@ -204,8 +238,7 @@ pub trait WitnessInput {
         }
 
         for (hashed_address, storage_trie) in state.storage_tries.iter() {
-            let account =
-                state.state_trie.get_rlp::<TrieAccount>(hashed_address.as_slice()).unwrap();
+            let account = state.state_trie.get_rlp::<TrieAccount>(hashed_address.as_slice())?;
             let storage_root = account.map_or(EMPTY_ROOT_HASH, |a| a.storage_root);
             if storage_root != storage_trie.hash() {
                 return Err(ClientError::MismatchedStorageRoot);
@@ -218,9 +251,9 @@ pub trait WitnessInput {
         // Verify and build block hashes
         let mut block_hashes: HashMap<u64, B256> = HashMap::with_hasher(Default::default());
         for (child_header, parent_header) in sealed_headers.iter().tuple_windows() {
-            if parent_header.number() != child_header.number() - 1 {
+            if child_header.number().checked_sub(1) != Some(parent_header.number()) {
                 return Err(ClientError::InvalidHeaderBlockNumber(
-                    parent_header.number() + 1,
+                    parent_header.number().saturating_add(1),
                     child_header.number(),
                 ));
             }
@@ -239,3 +272,73 @@ pub trait WitnessInput {
         Ok(TrieDB::new(state, block_hashes, bytecodes_by_hash))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use alloy_consensus::BlockBody;
+    use rsp_mpt::MptNode;
+
+    use super::*;
+
+    fn empty_state() -> EthereumState {
+        EthereumState { state_trie: MptNode::default(), storage_tries: HashMap::default() }
+    }
+
+    /// Returns an input of an empty block with the given number, on top of the given ancestors.
+    fn input(number: u64, ancestor_headers: Vec<Header>) -> EthClientExecutorInput {
+        let parent_hash = ancestor_headers.first().map(Header::hash_slow).unwrap_or_default();
+
+        EthClientExecutorInput {
+            current_block: Block {
+                header: Header { number, parent_hash, ..Default::default() },
+                body: BlockBody::default(),
+            },
+            ancestor_headers,
+            parent_state: empty_state(),
+            bytecodes: vec![],
+            genesis: Genesis::Mainnet,
+            custom_beneficiary: None,
+            opcode_tracking: false,
+        }
+    }
+
+    #[test]
+    fn test_witness_db_requires_a_parent_header() {
+        let input = input(1, vec![]);
+        let sealed_headers: Vec<_> = input.sealed_headers().collect();
+
+        let err = input.witness_db(&sealed_headers).unwrap_err();
+        assert!(matches!(err, ClientError::MissingParentHeader));
+    }
+
+    #[test]
+    fn test_witness_db_rejects_a_parent_of_the_genesis() {
+        let parent_header = Header { state_root: EMPTY_ROOT_HASH, ..Default::default() };
+        let input = input(0, vec![parent_header]);
+        let sealed_headers: Vec<_> = input.sealed_headers().collect();
+
+        let err = input.witness_db(&sealed_headers).unwrap_err();
+        assert!(matches!(err, ClientError::InvalidHeaderBlockNumber(1, 0)));
+    }
+
+    #[test]
+    fn test_missing_bytecode_is_recorded() {
+        let state = empty_state();
+        let db = TrieDB::new(&state, HashMap::default(), HashMap::default());
+
+        assert!(db.code_by_hash_ref(B256::repeat_byte(1)).is_err());
+        assert!(matches!(
+            db.take_error(),
+            Some(ClientError::MissingBytecodeByHash(hash)) if hash == B256::repeat_byte(1)
+        ));
+    }
+
+    #[test]
+    fn test_missing_block_hash_is_recorded() {
+        let state = empty_state();
+        let db = TrieDB::new(&state, HashMap::default(), HashMap::default());
+
+        assert!(db.block_hash_ref(42).is_err());
+        assert!(matches!(db.take_error(), Some(ClientError::MissingBlockHash(42))));
+    }
+}
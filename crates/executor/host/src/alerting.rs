@@ -1,54 +1,482 @@
+//! Alerting backends used by the long-running binaries.
+//!
 //! Can't use `pagerduty-rs` because the library is unmaintained.
 
+use std::{
+    collections::HashSet,
+    fmt::{Display, Formatter},
+    future::Future,
+    sync::Mutex,
+};
+
 use serde::Serialize;
-use tracing::error;
+use tracing::{error, info, warn};
+use url::Url;
+
+pub const PAGER_DUTY_ENDPOINT: &str = "https://events.pagerduty.com/v2";
+
+const ALERT_SOURCE: &str = "RSP";
+
+/// The severity of an alert, using the PagerDuty vocabulary.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AlertSeverity {
+    Critical,
+    Error,
+    Warning,
+    Info,
+}
 
-const PAGER_DUTY_ENDPOINT: &str = "https://events.pagerduty.com/v2";
+impl Display for AlertSeverity {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AlertSeverity::Critical => write!(f, "critical"),
+            AlertSeverity::Error => write!(f, "error"),
+            AlertSeverity::Warning => write!(f, "warning"),
+            AlertSeverity::Info => write!(f, "info"),
+        }
+    }
+}
+
+/// An alert to be sent to an [AlertingBackend].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Alert {
+    pub summary: String,
+    pub severity: AlertSeverity,
+    /// Alerts sharing the same dedup key are grouped into a single incident, which can later be
+    /// resolved with [AlertingClient::resolve].
+    pub dedup_key: Option<String>,
+}
+
+impl Alert {
+    pub fn new(summary: impl Into<String>, severity: AlertSeverity) -> Self {
+        Self { summary: summary.into(), severity, dedup_key: None }
+    }
+
+    /// Creates an alert deduplicated per block.
+    pub fn for_block(
+        chain_id: u64,
+        block_number: u64,
+        summary: impl Into<String>,
+        severity: AlertSeverity,
+    ) -> Self {
+        Self::new(summary, severity).with_dedup_key(block_dedup_key(chain_id, block_number))
+    }
+
+    /// Creates an alert deduplicated per error class, so that repeated failures of the same
+    /// kind are reported once until resolved.
+    pub fn for_error_class(
+        chain_id: u64,
+        error_class: &str,
+        summary: impl Into<String>,
+        severity: AlertSeverity,
+    ) -> Self {
+        Self::new(summary, severity).with_dedup_key(error_class_dedup_key(chain_id, error_class))
+    }
+
+    pub fn with_dedup_key(mut self, dedup_key: impl Into<String>) -> Self {
+        self.dedup_key = Some(dedup_key.into());
+        self
+    }
+}
 
+/// Returns the dedup key used for alerts related to a given block.
+pub fn block_dedup_key(chain_id: u64, block_number: u64) -> String {
+    format!("rsp-{chain_id}-block-{block_number}")
+}
+
+/// Returns the dedup key used for alerts related to a given error class.
+pub fn error_class_dedup_key(chain_id: u64, error_class: &str) -> String {
+    format!("rsp-{chain_id}-{error_class}")
+}
+
+/// A destination for alerts.
+///
+/// Delivery failures are logged and never returned as errors, alerting must not interfere with
+/// block processing.
+pub trait AlertingBackend: Send + Sync {
+    /// Triggers an alert, returning whether it was delivered.
+    fn trigger(&self, alert: &Alert) -> impl Future<Output = bool> + Send;
+
+    /// Resolves the incident opened for the given dedup key, returning whether it was delivered.
+    fn resolve(&self, dedup_key: &str) -> impl Future<Output = bool> + Send;
+}
+
+/// Sends alerts to the PagerDuty Events API v2.
 #[derive(Debug)]
-pub struct AlertingClient {
+pub struct PagerDutyBackend {
     client: reqwest::Client,
+    endpoint: String,
     routing_key: String,
 }
-impl AlertingClient {
+
+impl PagerDutyBackend {
     pub fn new(routing_key: String) -> Self {
-        Self { client: reqwest::Client::new(), routing_key }
+        Self {
+            client: reqwest::Client::new(),
+            endpoint: PAGER_DUTY_ENDPOINT.to_string(),
+            routing_key,
+        }
     }
 
-    /// Send an alert to the PageDuty endpoint.
-    pub async fn send_alert(&self, summary: String) {
-        let alert = PagerDutyAlert {
-            payload: PagerDutyAlertPayload {
-                summary,
-                severity: "error".to_string(),
-                source: "RSP".to_string(),
+    /// Overrides the PagerDuty endpoint, mostly useful to target a local stand-in.
+    pub fn with_endpoint(mut self, endpoint: &Url) -> Self {
+        self.endpoint = endpoint.as_str().trim_end_matches('/').to_string();
+        self
+    }
+
+    async fn enqueue(&self, event: &PagerDutyEvent) -> bool {
+        match self.client.post(format!("{}/enqueue", self.endpoint)).json(event).send().await {
+            Ok(response) => match response.error_for_status() {
+                Ok(_) => true,
+                Err(err) => {
+                    error!("PG returned an error: {err}");
+                    false
+                }
             },
+            Err(err) => {
+                error!("Error sending alert: {err}");
+                false
+            }
+        }
+    }
+}
+
+impl AlertingBackend for PagerDutyBackend {
+    async fn trigger(&self, alert: &Alert) -> bool {
+        let event = PagerDutyEvent {
+            payload: Some(PagerDutyEventPayload {
+                summary: alert.summary.clone(),
+                severity: alert.severity.to_string(),
+                source: ALERT_SOURCE.to_string(),
+            }),
             routing_key: self.routing_key.clone(),
             event_action: "trigger".to_string(),
+            dedup_key: alert.dedup_key.clone(),
         };
 
-        match self.client.post(format!("{}/enqueue", PAGER_DUTY_ENDPOINT)).json(&alert).send().await
-        {
-            Ok(response) => {
-                if let Err(err) = response.error_for_status() {
-                    error!("PG returned an error: {err}");
+        self.enqueue(&event).await
+    }
+
+    async fn resolve(&self, dedup_key: &str) -> bool {
+        let event = PagerDutyEvent {
+            payload: None,
+            routing_key: self.routing_key.clone(),
+            event_action: "resolve".to_string(),
+            dedup_key: Some(dedup_key.to_string()),
+        };
+
+        self.enqueue(&event).await
+    }
+}
+
+/// Posts alerts as JSON to a generic webhook.
+///
+/// The message is sent both in the `text` (Slack) and `content` (Discord) fields, so the same
+/// backend works with both incoming webhook flavours.
+#[derive(Debug)]
+pub struct WebhookBackend {
+    client: reqwest::Client,
+    url: Url,
+}
+
+impl WebhookBackend {
+    pub fn new(url: Url) -> Self {
+        Self { client: reqwest::Client::new(), url }
+    }
+
+    async fn post(&self, message: String) -> bool {
+        let body = WebhookMessage { text: message.clone(), content: message };
+
+        match self.client.post(self.url.clone()).json(&body).send().await {
+            Ok(response) => match response.error_for_status() {
+                Ok(_) => true,
+                Err(err) => {
+                    error!("Webhook returned an error: {err}");
+                    false
                 }
+            },
+            Err(err) => {
+                error!("Error sending alert: {err}");
+                false
+            }
+        }
+    }
+}
+
+impl AlertingBackend for WebhookBackend {
+    async fn trigger(&self, alert: &Alert) -> bool {
+        self.post(format!("[{}] {}", alert.severity.to_string().to_uppercase(), alert.summary))
+            .await
+    }
+
+    async fn resolve(&self, dedup_key: &str) -> bool {
+        self.post(format!("[RESOLVED] {dedup_key}")).await
+    }
+}
+
+/// Only logs the alerts, useful for local runs.
+#[derive(Debug, Default)]
+pub struct LogBackend;
+
+impl AlertingBackend for LogBackend {
+    async fn trigger(&self, alert: &Alert) -> bool {
+        match alert.severity {
+            AlertSeverity::Critical | AlertSeverity::Error => {
+                error!(dedup_key = ?alert.dedup_key, "Alert: {}", alert.summary)
             }
-            Err(err) => error!("Error sending alert: {err}"),
+            AlertSeverity::Warning => {
+                warn!(dedup_key = ?alert.dedup_key, "Alert: {}", alert.summary)
+            }
+            AlertSeverity::Info => info!(dedup_key = ?alert.dedup_key, "Alert: {}", alert.summary),
+        }
+
+        true
+    }
+
+    async fn resolve(&self, dedup_key: &str) -> bool {
+        info!(dedup_key, "Alert resolved");
+
+        true
+    }
+}
+
+/// The alerting backend selected at startup.
+#[derive(Debug)]
+pub enum AlertingBackendKind {
+    PagerDuty(PagerDutyBackend),
+    Webhook(WebhookBackend),
+    Log(LogBackend),
+}
+
+impl AlertingBackend for AlertingBackendKind {
+    async fn trigger(&self, alert: &Alert) -> bool {
+        match self {
+            AlertingBackendKind::PagerDuty(backend) => backend.trigger(alert).await,
+            AlertingBackendKind::Webhook(backend) => backend.trigger(alert).await,
+            AlertingBackendKind::Log(backend) => backend.trigger(alert).await,
+        }
+    }
+
+    async fn resolve(&self, dedup_key: &str) -> bool {
+        match self {
+            AlertingBackendKind::PagerDuty(backend) => backend.resolve(dedup_key).await,
+            AlertingBackendKind::Webhook(backend) => backend.resolve(dedup_key).await,
+            AlertingBackendKind::Log(backend) => backend.resolve(dedup_key).await,
+        }
+    }
+}
+
+/// Sends alerts to the configured backend, deduplicating them by dedup key.
+///
+/// An alert whose dedup key is already open is not sent again, and resolving a dedup key is only
+/// forwarded to the backend if an alert was delivered for it. An alert that failed to be
+/// delivered doesn't open an incident, so it is sent again the next time it is triggered, and an
+/// incident whose resolution failed to be delivered stays open, so it is resolved again the next
+/// time.
+#[derive(Debug)]
+pub struct AlertingClient {
+    backend: AlertingBackendKind,
+    open_incidents: Mutex<HashSet<String>>,
+}
+
+impl AlertingClient {
+    /// Creates a client sending alerts to PagerDuty.
+    pub fn new(routing_key: String) -> Self {
+        Self::with_backend(AlertingBackendKind::PagerDuty(PagerDutyBackend::new(routing_key)))
+    }
+
+    pub fn with_backend(backend: AlertingBackendKind) -> Self {
+        Self { backend, open_incidents: Mutex::new(HashSet::new()) }
+    }
+
+    /// Creates a client from the CLI options, preferring PagerDuty over the webhook. Returns
+    /// `None` when no backend is configured and `log_only` is false.
+    pub fn from_options(
+        pager_duty_integration_key: Option<String>,
+        pager_duty_endpoint: Option<&Url>,
+        webhook_url: Option<Url>,
+        log_only: bool,
+    ) -> Option<Self> {
+        let backend = match (pager_duty_integration_key, webhook_url) {
+            (Some(routing_key), _) => {
+                let backend = PagerDutyBackend::new(routing_key);
+                let backend = match pager_duty_endpoint {
+                    Some(endpoint) => backend.with_endpoint(endpoint),
+                    None => backend,
+                };
+                AlertingBackendKind::PagerDuty(backend)
+            }
+            (None, Some(url)) => AlertingBackendKind::Webhook(WebhookBackend::new(url)),
+            (None, None) if log_only => AlertingBackendKind::Log(LogBackend),
+            (None, None) => return None,
+        };
+
+        Some(Self::with_backend(backend))
+    }
+
+    /// Send an alert with the "error" severity.
+    pub async fn send_alert(&self, summary: String) {
+        self.trigger(&Alert::new(summary, AlertSeverity::Error)).await
+    }
+
+    /// Send an alert to the backend, unless an incident with the same dedup key is open.
+    pub async fn trigger(&self, alert: &Alert) {
+        if let Some(dedup_key) = &alert.dedup_key {
+            if self.open_incidents.lock().unwrap().contains(dedup_key) {
+                return;
+            }
+        }
+
+        if self.backend.trigger(alert).await {
+            if let Some(dedup_key) = &alert.dedup_key {
+                self.open_incidents.lock().unwrap().insert(dedup_key.clone());
+            }
+        }
+    }
+
+    /// Resolves the incident opened for the given dedup key, if any.
+    pub async fn resolve(&self, dedup_key: &str) {
+        if !self.open_incidents.lock().unwrap().contains(dedup_key) {
+            return;
+        }
+
+        if self.backend.resolve(dedup_key).await {
+            self.open_incidents.lock().unwrap().remove(dedup_key);
         }
     }
 }
 
 #[derive(Debug, Clone, Serialize)]
-struct PagerDutyAlert {
-    payload: PagerDutyAlertPayload,
+struct PagerDutyEvent {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    payload: Option<PagerDutyEventPayload>,
     routing_key: String,
     event_action: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    dedup_key: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize)]
-struct PagerDutyAlertPayload {
+struct PagerDutyEventPayload {
     summary: String,
     severity: String,
     source: String,
 }
+
+#[derive(Debug, Clone, Serialize)]
+struct WebhookMessage {
+    text: String,
+    content: String,
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::sync::mpsc;
+
+    use super::*;
+    use crate::test_utils::{spawn_mock_server, MockRequest};
+
+    /// Spawns a stand-in answering `500 Internal Server Error` to the first `failures` requests
+    /// and `202 Accepted` to the following ones, and forwarding the request bodies to the
+    /// returned channel.
+    async fn spawn_stand_in(failures: usize) -> (Url, mpsc::UnboundedReceiver<MockRequest>) {
+        let mut responses = vec![(500, String::new()); failures];
+        responses.push((202, String::new()));
+
+        spawn_mock_server(responses).await
+    }
+
+    fn body(request: MockRequest) -> serde_json::Value {
+        serde_json::from_str(&request.body).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_pager_duty_dedup_and_resolve() {
+        let (url, mut rx) = spawn_stand_in(0).await;
+        let client =
+            AlertingClient::from_options(Some("key".to_string()), Some(&url), None, false).unwrap();
+
+        let alert = Alert::for_error_class(1, "execution", "failed", AlertSeverity::Critical);
+        client.trigger(&alert).await;
+        // Deduplicated, not sent.
+        client.trigger(&alert).await;
+        client.resolve(&error_class_dedup_key(1, "execution")).await;
+        // Already resolved, not sent.
+        client.resolve(&error_class_dedup_key(1, "execution")).await;
+
+        let trigger = body(rx.recv().await.unwrap());
+        assert_eq!(trigger["event_action"], "trigger");
+        assert_eq!(trigger["dedup_key"], "rsp-1-execution");
+        assert_eq!(trigger["payload"]["severity"], "critical");
+
+        let resolve = body(rx.recv().await.unwrap());
+        assert_eq!(resolve["event_action"], "resolve");
+        assert_eq!(resolve["dedup_key"], "rsp-1-execution");
+        assert!(resolve.get("payload").is_none());
+
+        assert!(rx.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_failed_delivery_is_retried() {
+        let (url, mut rx) = spawn_stand_in(1).await;
+        let client =
+            AlertingClient::from_options(Some("key".to_string()), Some(&url), None, false).unwrap();
+
+        let alert = Alert::for_block(1, 42, "failed", AlertSeverity::Error);
+        // Rejected by the backend, no incident is opened.
+        client.trigger(&alert).await;
+        client.resolve(&block_dedup_key(1, 42)).await;
+        // Sent again, then deduplicated.
+        client.trigger(&alert).await;
+        client.trigger(&alert).await;
+
+        for _ in 0..2 {
+            let trigger = body(rx.recv().await.unwrap());
+            assert_eq!(trigger["event_action"], "trigger");
+            assert_eq!(trigger["dedup_key"], "rsp-1-block-42");
+        }
+
+        assert!(rx.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_failed_resolution_is_retried() {
+        let (url, mut rx) = spawn_mock_server(vec![
+            (202, String::new()),
+            (500, String::new()),
+            (202, String::new()),
+        ])
+        .await;
+        let client =
+            AlertingClient::from_options(Some("key".to_string()), Some(&url), None, false).unwrap();
+
+        client.trigger(&Alert::for_block(1, 42, "failed", AlertSeverity::Error)).await;
+        // Rejected by the backend, the incident stays open.
+        client.resolve(&block_dedup_key(1, 42)).await;
+        // Sent again, then already resolved.
+        client.resolve(&block_dedup_key(1, 42)).await;
+        client.resolve(&block_dedup_key(1, 42)).await;
+
+        assert_eq!(body(rx.recv().await.unwrap())["event_action"], "trigger");
+        for _ in 0..2 {
+            let resolve = body(rx.recv().await.unwrap());
+            assert_eq!(resolve["event_action"], "resolve");
+            assert_eq!(resolve["dedup_key"], "rsp-1-block-42");
+        }
+
+        assert!(rx.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_webhook() {
+        let (url, mut rx) = spawn_stand_in(0).await;
+        let client = AlertingClient::from_options(None, None, Some(url), false).unwrap();
+
+        client.trigger(&Alert::for_block(1, 42, "boom", AlertSeverity::Warning)).await;
+
+        let message = body(rx.recv().await.unwrap());
+        assert_eq!(message["text"], "[WARNING] boom");
+        assert_eq!(message["content"], "[WARNING] boom");
+    }
+}
@@ -0,0 +1,184 @@
+use std::{
+    cell::RefCell,
+    collections::BTreeSet,
+    fmt::{self, Display},
+};
+
+use reth_errors::ProviderError;
+use revm::{
+    state::{AccountInfo, Bytecode},
+    DatabaseRef,
+};
+use revm_primitives::{Address, B256, U256};
+use rsp_client_executor::io::TrieDB;
+
+/// The data read while executing a block that cannot be resolved from the client input.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MissingWitness {
+    /// The accounts whose path in the state trie is not resolved.
+    pub accounts: BTreeSet<Address>,
+    /// The storage slots whose storage trie is missing, or whose path is not resolved.
+    pub storage: BTreeSet<(Address, U256)>,
+    /// The hashes of the missing bytecodes.
+    pub bytecodes: BTreeSet<B256>,
+    /// The numbers of the blocks whose hash is missing.
+    pub block_hashes: BTreeSet<u64>,
+}
+
+impl MissingWitness {
+    /// Returns true if the whole execution could be resolved from the client input.
+    pub fn is_empty(&self) -> bool {
+        self.accounts.is_empty() &&
+            self.storage.is_empty() &&
+            self.bytecodes.is_empty() &&
+            self.block_hashes.is_empty()
+    }
+}
+
+impl Display for MissingWitness {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut items = Vec::new();
+        items.extend(self.accounts.iter().map(|address| format!("account {address}")));
+        items.extend(
+            self.storage.iter().map(|(address, index)| format!("slot {index} of {address}")),
+        );
+        items.extend(self.bytecodes.iter().map(|hash| format!("bytecode {hash}")));
+        items.extend(self.block_hashes.iter().map(|number| format!("hash of block {number}")));
+
+        write!(f, "{}", items.join(", "))
+    }
+}
+
+/// A database that reads from the same [`TrieDB`] as the client, but records the data it cannot
+/// resolve instead of failing, so a single execution reports everything the input is missing.
+///
+/// The unresolved data is replaced by default values, so the execution may diverge from the
+/// honest one after the first miss.
+#[derive(Debug)]
+pub(crate) struct CheckingDb<'a> {
+    inner: TrieDB<'a>,
+    missing: RefCell<MissingWitness>,
+}
+
+impl<'a> CheckingDb<'a> {
+    pub(crate) fn new(inner: TrieDB<'a>) -> Self {
+        Self { inner, missing: RefCell::new(MissingWitness::default()) }
+    }
+
+    /// Returns the data that could not be resolved so far.
+    pub(crate) fn into_missing(self) -> MissingWitness {
+        self.missing.into_inner()
+    }
+}
+
+impl DatabaseRef for CheckingDb<'_> {
+    type Error = ProviderError;
+
+    fn basic_ref(&self, address: Address) -> Result<Option<AccountInfo>, Self::Error> {
+        Ok(self.inner.basic_ref(address).unwrap_or_else(|_| {
+            self.missing.borrow_mut().accounts.insert(address);
+            None
+        }))
+    }
+
+    fn code_by_hash_ref(&self, hash: B256) -> Result<Bytecode, Self::Error> {
+        Ok(self.inner.code_by_hash_ref(hash).unwrap_or_else(|_| {
+            self.missing.borrow_mut().bytecodes.insert(hash);
+            Bytecode::default()
+        }))
+    }
+
+    fn storage_ref(&self, address: Address, index: U256) -> Result<U256, Self::Error> {
+        Ok(self.inner.storage_ref(address, index).unwrap_or_else(|_| {
+            self.missing.borrow_mut().storage.insert((address, index));
+            U256::ZERO
+        }))
+    }
+
+    fn block_hash_ref(&self, number: u64) -> Result<B256, Self::Error> {
+        Ok(self.inner.block_hash_ref(number).unwrap_or_else(|_| {
+            self.missing.borrow_mut().block_hashes.insert(number);
+            B256::ZERO
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy_primitives::{keccak256, map::HashMap};
+    use reth_trie::{TrieAccount, EMPTY_ROOT_HASH, KECCAK_EMPTY};
+    use rsp_mpt::{EthereumState, FlatWitness, MptNode};
+
+    use super::*;
+
+    fn account(nonce: u64) -> TrieAccount {
+        TrieAccount {
+            nonce,
+            balance: U256::ZERO,
+            storage_root: EMPTY_ROOT_HASH,
+            code_hash: KECCAK_EMPTY,
+        }
+    }
+
+    #[test]
+    fn test_checking_db_records_missing_data() {
+        let present = Address::repeat_byte(1);
+        let without_storage_trie = Address::repeat_byte(2);
+
+        // the storage trie of the second account is not provided
+        let mut state_trie = MptNode::default();
+        state_trie.insert_rlp(keccak256(present).as_slice(), account(1)).unwrap();
+        state_trie
+            .insert_rlp(
+                keccak256(without_storage_trie).as_slice(),
+                TrieAccount { storage_root: B256::repeat_byte(2), ..account(2) },
+            )
+            .unwrap();
+
+        let mut storage_tries = HashMap::default();
+        storage_tries.insert(keccak256(present), MptNode::default());
+        let state = EthereumState { state_trie, storage_tries };
+
+        let mut block_hashes = HashMap::default();
+        block_hashes.insert(1, B256::repeat_byte(1));
+        let db = CheckingDb::new(TrieDB::new(&state, block_hashes, HashMap::default()));
+
+        assert_eq!(db.basic_ref(present).unwrap().unwrap().nonce, 1);
+        assert_eq!(db.storage_ref(present, U256::from(1)).unwrap(), U256::ZERO);
+        assert_eq!(db.storage_ref(without_storage_trie, U256::from(1)).unwrap(), U256::ZERO);
+        assert_eq!(db.storage_ref(Address::repeat_byte(3), U256::from(1)).unwrap(), U256::ZERO);
+        assert_eq!(db.code_by_hash_ref(B256::repeat_byte(3)).unwrap(), Bytecode::default());
+        assert_eq!(db.block_hash_ref(1).unwrap(), B256::repeat_byte(1));
+        assert_eq!(db.block_hash_ref(2).unwrap(), B256::ZERO);
+
+        let missing = db.into_missing();
+        assert!(missing.accounts.is_empty());
+        assert_eq!(missing.storage, BTreeSet::from([(without_storage_trie, U256::from(1))]));
+        assert_eq!(missing.bytecodes, BTreeSet::from([B256::repeat_byte(3)]));
+        assert_eq!(missing.block_hashes, BTreeSet::from([2]));
+    }
+
+    #[test]
+    fn test_checking_db_records_unresolved_accounts() {
+        let mut state_trie = MptNode::default();
+        for i in 0..16u8 {
+            state_trie
+                .insert_rlp(keccak256(Address::repeat_byte(i)).as_slice(), account(1))
+                .unwrap();
+        }
+
+        // only keep the root hash of the state trie, as in an incomplete witness
+        let state =
+            FlatWitness { state_root: state_trie.hash(), storage_roots: vec![], nodes: vec![] }
+                .into_state()
+                .unwrap();
+        let db = CheckingDb::new(TrieDB::new(&state, HashMap::default(), HashMap::default()));
+
+        assert_eq!(db.basic_ref(Address::repeat_byte(1)).unwrap(), None);
+
+        let missing = db.into_missing();
+        assert_eq!(missing.accounts, BTreeSet::from([Address::repeat_byte(1)]));
+        assert!(!missing.is_empty());
+        assert_eq!(missing.to_string(), format!("account {}", Address::repeat_byte(1)));
+    }
+}
@@ -1,10 +1,15 @@
-use alloy_rpc_types::ConversionError;
+use std::time::Duration;
+
+use alloy_rpc_types::{BlockId, ConversionError};
 use alloy_transport::TransportError;
 use reth_errors::BlockExecutionError;
 use revm_primitives::B256;
-use rsp_mpt::FromProofError;
+use rsp_client_executor::error::ClientError;
+use rsp_mpt::{FromProofError, StateError};
 use rsp_rpc_db::RpcDbError;
 
+use crate::MissingWitness;
+
 #[derive(Debug, thiserror::Error)]
 pub enum Error {
     #[error("Failed to parse blocks into executor friendly format {0}")]
@@ -23,10 +28,24 @@ pub enum Error {
     FromProof(#[from] FromProofError),
     #[error("RPC didnt have expected block height {0}")]
     ExpectedBlock(u64),
+    #[error("RPC didnt have expected block {0}")]
+    ExpectedBlockId(BlockId),
     #[error("Header Mismatch \n found {0} expected {1}")]
     HeaderMismatch(B256, B256),
     #[error("State root mismatch after local execution \n found {0} expected {1}")]
     StateRootMismatch(B256, B256),
+    #[error("Failed to update the state: {0}")]
+    StateUpdate(#[from] StateError),
+    #[error("Invalid client input: {0}")]
+    InvalidClientInput(#[from] ClientError),
+    #[error("The client input is missing data read by the block execution: {0}")]
+    IncompleteWitness(MissingWitness),
+    #[error("Client execution failed in the zkVM: {0}")]
+    ClientExecutionFailed(String),
+    #[error("Proof verification failed: {0}")]
+    ProofVerificationFailed(String),
+    #[error("Remote proving job {0} timed out after {1:?}")]
+    RemoteProvingTimedOut(String, Duration),
     #[error("Failed to read the genesis file: {0}")]
     FailedToReadGenesisFile(#[from] std::io::Error),
 }
@@ -2,6 +2,7 @@ use std::marker::PhantomData;
 
 use alloy_network::Ethereum;
 use alloy_provider::Network;
+use either::{for_both, Either};
 use eyre::{eyre, Ok};
 use op_alloy_network::Optimism;
 use reth_chainspec::ChainSpec;
@@ -15,15 +16,15 @@ use reth_primitives_traits::NodePrimitives;
 use rsp_client_executor::{custom::CustomEvmFactory, BlockValidator, IntoInput, IntoPrimitives};
 use rsp_primitives::genesis::Genesis;
 use serde::de::DeserializeOwned;
-use sp1_prover::components::CpuProverComponents;
 use sp1_sdk::{
-    CudaProver, EnvProver, Prover, SP1ProofMode, SP1ProofWithPublicValues, SP1ProvingKey, SP1Stdin,
+    CpuProver, CudaProver, EnvProver, ExecutionReport, Prover, SP1ProofMode,
+    SP1ProofWithPublicValues, SP1ProvingKey, SP1PublicValues, SP1Stdin, SP1VerifyingKey,
 };
 
 use crate::ExecutionHooks;
 
 pub trait ExecutorComponents {
-    type Prover: Prover<CpuProverComponents> + MaybeProveWithCycles + 'static;
+    type Prover: BlockProver;
 
     type Network: Network;
 
@@ -42,16 +43,51 @@ pub trait ExecutorComponents {
     fn try_into_chain_spec(genesis: &Genesis) -> eyre::Result<Self::ChainSpec>;
 }
 
-pub trait MaybeProveWithCycles {
+/// The prover used to set up, execute and prove the client program.
+///
+/// Implemented by the local SP1 provers, and by [`RemoteProver`](crate::remote_prover::RemoteProver)
+/// with the `remote-prover` feature. The methods are blocking, and are called from blocking
+/// threads.
+pub trait BlockProver: Send + Sync + 'static {
+    /// Generates the proving and verifying keys of the program.
+    fn setup(&self, elf: &[u8]) -> (SP1ProvingKey, SP1VerifyingKey);
+
+    /// Executes the program without generating a proof.
+    fn execute(
+        &self,
+        elf: &[u8],
+        stdin: &SP1Stdin,
+    ) -> Result<(SP1PublicValues, ExecutionReport), eyre::Error>;
+
+    /// Generates a proof, along with its cycle count if reported by the prover.
     fn prove_with_cycles(
         &self,
         pk: &SP1ProvingKey,
         stdin: &SP1Stdin,
         mode: SP1ProofMode,
     ) -> Result<(SP1ProofWithPublicValues, Option<u64>), eyre::Error>;
+
+    /// Verifies a proof against the verifying key.
+    fn verify(
+        &self,
+        proof: &SP1ProofWithPublicValues,
+        vk: &SP1VerifyingKey,
+    ) -> Result<(), eyre::Error>;
 }
 
-impl MaybeProveWithCycles for EnvProver {
+impl BlockProver for EnvProver {
+    fn setup(&self, elf: &[u8]) -> (SP1ProvingKey, SP1VerifyingKey) {
+        Prover::setup(self, elf)
+    }
+
+    fn execute(
+        &self,
+        elf: &[u8],
+        stdin: &SP1Stdin,
+    ) -> Result<(SP1PublicValues, ExecutionReport), eyre::Error> {
+        Prover::execute(self, elf, stdin).map_err(|err| eyre!("{err}"))
+    }
+
     fn prove_with_cycles(
         &self,
         pk: &SP1ProvingKey,
@@ -62,9 +98,62 @@ impl MaybeProveWithCycles for EnvProver {
 
         Ok((proof, None))
     }
+
+    fn verify(
+        &self,
+        proof: &SP1ProofWithPublicValues,
+        vk: &SP1VerifyingKey,
+    ) -> Result<(), eyre::Error> {
+        Prover::verify(self, proof, vk).map_err(|err| eyre!("{err}"))
+    }
 }
 
-impl MaybeProveWithCycles for CudaProver {
+impl BlockProver for CpuProver {
+    fn setup(&self, elf: &[u8]) -> (SP1ProvingKey, SP1VerifyingKey) {
+        Prover::setup(self, elf)
+    }
+
+    fn execute(
+        &self,
+        elf: &[u8],
+        stdin: &SP1Stdin,
+    ) -> Result<(SP1PublicValues, ExecutionReport), eyre::Error> {
+        Prover::execute(self, elf, stdin).map_err(|err| eyre!("{err}"))
+    }
+
+    fn prove_with_cycles(
+        &self,
+        pk: &SP1ProvingKey,
+        stdin: &SP1Stdin,
+        mode: SP1ProofMode,
+    ) -> Result<(SP1ProofWithPublicValues, Option<u64>), eyre::Error> {
+        let proof = self.prove(pk, stdin).mode(mode).run().map_err(|err| eyre!("{err}"))?;
+
+        Ok((proof, None))
+    }
+
+    fn verify(
+        &self,
+        proof: &SP1ProofWithPublicValues,
+        vk: &SP1VerifyingKey,
+    ) -> Result<(), eyre::Error> {
+        Prover::verify(self, proof, vk).map_err(|err| eyre!("{err}"))
+    }
+}
+
+impl BlockProver for CudaProver {
+    fn setup(&self, elf: &[u8]) -> (SP1ProvingKey, SP1VerifyingKey) {
+        Prover::setup(self, elf)
+    }
+
+    fn execute(
+        &self,
+        elf: &[u8],
+        stdin: &SP1Stdin,
+    ) -> Result<(SP1PublicValues, ExecutionReport), eyre::Error> {
+        Prover::execute(self, elf, stdin).map_err(|err| eyre!("{err}"))
+    }
+
     fn prove_with_cycles(
         &self,
         pk: &SP1ProvingKey,
@@ -72,10 +161,55 @@ impl MaybeProveWithCycles for CudaProver {
         mode: SP1ProofMode,
     ) -> Result<(SP1ProofWithPublicValues, Option<u64>), eyre::Error> {
         let (proof, cycles) =
-            self.prove_with_cycles(pk, stdin, mode).map_err(|err| eyre!("{err}"))?;
+            CudaProver::prove_with_cycles(self, pk, stdin, mode).map_err(|err| eyre!("{err}"))?;
 
         Ok((proof, Some(cycles)))
     }
+
+    fn verify(
+        &self,
+        proof: &SP1ProofWithPublicValues,
+        vk: &SP1VerifyingKey,
+    ) -> Result<(), eyre::Error> {
+        Prover::verify(self, proof, vk).map_err(|err| eyre!("{err}"))
+    }
+}
+
+/// Selects one of two provers at runtime, e.g. a local prover or a
+/// [`RemoteProver`](crate::remote_prover::RemoteProver).
+impl<L, R> BlockProver for Either<L, R>
+where
+    L: BlockProver,
+    R: BlockProver,
+{
+    fn setup(&self, elf: &[u8]) -> (SP1ProvingKey, SP1VerifyingKey) {
+        for_both!(self, prover => prover.setup(elf))
+    }
+
+    fn execute(
+        &self,
+        elf: &[u8],
+        stdin: &SP1Stdin,
+    ) -> Result<(SP1PublicValues, ExecutionReport), eyre::Error> {
+        for_both!(self, prover => prover.execute(elf, stdin))
+    }
+
+    fn prove_with_cycles(
+        &self,
+        pk: &SP1ProvingKey,
+        stdin: &SP1Stdin,
+        mode: SP1ProofMode,
+    ) -> Result<(SP1ProofWithPublicValues, Option<u64>), eyre::Error> {
+        for_both!(self, prover => prover.prove_with_cycles(pk, stdin, mode))
+    }
+
+    fn verify(
+        &self,
+        proof: &SP1ProofWithPublicValues,
+        vk: &SP1VerifyingKey,
+    ) -> Result<(), eyre::Error> {
+        for_both!(self, prover => prover.verify(proof, vk))
+    }
 }
 
 #[derive(Debug, Default)]
@@ -86,7 +220,7 @@ pub struct EthExecutorComponents<H, P = EnvProver> {
 impl<H, P> ExecutorComponents for EthExecutorComponents<H, P>
 where
     H: ExecutionHooks,
-    P: Prover<CpuProverComponents> + MaybeProveWithCycles + 'static,
+    P: BlockProver,
 {
     type Prover = P;
 
@@ -114,7 +248,7 @@ pub struct OpExecutorComponents<H, P = EnvProver> {
 impl<H, P> ExecutorComponents for OpExecutorComponents<H, P>
 where
     H: ExecutionHooks,
-    P: Prover<CpuProverComponents> + MaybeProveWithCycles + 'static,
+    P: BlockProver,
 {
     type Prover = P;
 
@@ -5,20 +5,28 @@ use std::{
     time::{Duration, Instant},
 };
 
+use alloy_consensus::BlockHeader;
+use alloy_network::{BlockResponse, HeaderResponse};
+use alloy_primitives::B256;
 use alloy_provider::Provider;
+use alloy_rpc_types::{BlockId, BlockNumberOrTag};
 use either::Either;
 use eyre::bail;
 use reth_primitives_traits::NodePrimitives;
 use rsp_client_executor::io::{ClientExecutorInput, CommittedHeader};
-use serde::de::DeserializeOwned;
-use sp1_prover::components::CpuProverComponents;
-use sp1_sdk::{ExecutionReport, Prover, SP1ProvingKey, SP1PublicValues, SP1Stdin, SP1VerifyingKey};
+use serde::{de::DeserializeOwned, Serialize};
+use sp1_sdk::{
+    ExecutionReport, HashableKey, SP1ProofMode, SP1ProofWithPublicValues, SP1ProvingKey,
+    SP1PublicValues, SP1Stdin, SP1VerifyingKey,
+};
 use tokio::{task, time::sleep};
 use tracing::{info, info_span, warn};
 
 use crate::{
-    executor_components::MaybeProveWithCycles, Config, ExecutionHooks, ExecutorComponents,
-    HostError, HostExecutor,
+    executor_components::BlockProver,
+    proof_cache::{save_proof_to_cache, try_load_proof_from_cache, ProofMetadata},
+    proof_mode::proof_mode_name,
+    Config, ExecutionHooks, ExecutorComponents, HostError, HostExecutor,
 };
 
 pub type EitherExecutor<C, P> = Either<FullExecutor<C, P>, CachedExecutor<C>>;
@@ -51,8 +59,9 @@ where
 }
 
 pub trait BlockExecutor<C: ExecutorComponents> {
+    /// Executes the block with the given id, and returns its hash.
     #[allow(async_fn_in_trait)]
-    async fn execute(&self, block_number: u64) -> eyre::Result<()>;
+    async fn execute(&self, block_id: BlockId) -> eyre::Result<B256>;
 
     fn client(&self) -> Arc<C::Prover>;
 
@@ -67,7 +76,9 @@ pub trait BlockExecutor<C: ExecutorComponents> {
         &self,
         client_input: ClientExecutorInput<C::Primitives>,
         hooks: &C::Hooks,
-    ) -> eyre::Result<()> {
+    ) -> eyre::Result<B256> {
+        let block_hash = client_input.current_block.header.hash_slow();
+
         // Generate the proof.
         // Execute the block inside the zkVM.
         let mut stdin = SP1Stdin::new();
@@ -93,10 +104,9 @@ pub trait BlockExecutor<C: ExecutorComponents> {
             // Read the block header.
             let header = public_values.read::<CommittedHeader>().header;
             let executed_block_hash = header.hash_slow();
-            let input_block_hash = client_input.current_block.header.hash_slow();
 
-            if input_block_hash != executed_block_hash {
-                return Err(HostError::HeaderMismatch(executed_block_hash, input_block_hash))?
+            if block_hash != executed_block_hash {
+                return Err(HostError::HeaderMismatch(executed_block_hash, block_hash))?;
             }
 
             info!(?executed_block_hash, "Execution successful");
@@ -107,38 +117,112 @@ pub trait BlockExecutor<C: ExecutorComponents> {
         }
 
         if let Some(prove_mode) = self.config().prove_mode {
-            info!("Starting proof generation");
+            let block_number = client_input.current_block.number;
+            let chain_id = self.config().chain.id();
+
+            let cached_proof = match &self.config().cache_dir {
+                Some(cache_dir) => {
+                    load_cached_proof(
+                        cache_dir.clone(),
+                        chain_id,
+                        block_hash,
+                        prove_mode,
+                        self.client(),
+                        self.vk(),
+                    )
+                    .await
+                }
+                None => None,
+            };
+
+            hooks.on_proving_start(block_number).await?;
+
+            // The hooks are run for the cached proofs as well, so they are handled like the
+            // generated ones.
+            let (proof, cycle_count, proving_duration) = match cached_proof {
+                Some((proof, metadata)) => {
+                    info!("Valid proof found in cache, skipping proof generation");
 
-            let proving_start = Instant::now();
-            hooks.on_proving_start(client_input.current_block.number).await?;
-            let client = self.client();
-            let pk = self.pk();
+                    (
+                        proof,
+                        metadata.cycle_count,
+                        Duration::from_millis(metadata.proving_duration_ms),
+                    )
+                }
+                None => {
+                    info!("Starting proof generation");
+
+                    let proving_start = Instant::now();
+                    let client = self.client();
+                    let pk = self.pk();
+
+                    let (proof, cycle_count) = task::spawn_blocking(move || {
+                        client.prove_with_cycles(pk.as_ref(), &stdin, prove_mode)
+                    })
+                    .await
+                    .map_err(|err| eyre::eyre!("{err}"))??;
+
+                    let proving_duration = proving_start.elapsed();
+
+                    let proof = if self.config().verify_proof {
+                        let client = self.client();
+                        let vk = self.vk();
+
+                        let proof = task::spawn_blocking(move || {
+                            verify_proof(client.as_ref(), vk.as_ref(), &proof, block_hash)
+                                .map(|_| proof)
+                        })
+                        .await
+                        .map_err(|err| eyre::eyre!("{err}"))??;
+
+                        info!("Proof successfully verified");
+                        proof
+                    } else {
+                        proof
+                    };
+
+                    // Save the proof before running the hooks, so it's not generated again if
+                    // they fail.
+                    if let Some(cache_dir) = &self.config().cache_dir {
+                        let metadata = ProofMetadata {
+                            block_hash,
+                            vk_hash: B256::from(self.vk().bytes32_raw()),
+                            cycle_count,
+                            proving_duration_ms: proving_duration.as_millis() as u64,
+                            proof_mode: proof_mode_name(prove_mode).to_string(),
+                        };
+
+                        save_proof_to_cache(cache_dir, chain_id, &proof, &metadata)?;
+                    }
 
-            let (proof, cycle_count) = task::spawn_blocking(move || {
-                client
-                    .prove_with_cycles(pk.as_ref(), &stdin, prove_mode)
-                    .map_err(|err| eyre::eyre!("{err}"))
-            })
-            .await
-            .map_err(|err| eyre::eyre!("{err}"))??;
+                    info!("Proof successfully generated!");
 
-            let proving_duration = proving_start.elapsed();
-            let proof_bytes = bincode::serialize(&proof.proof).unwrap();
+                    (proof, cycle_count, proving_duration)
+                }
+            };
+
+            // The on-chain verifiers expect the encoded proof rather than the SP1 proof.
+            let proof_bytes = match prove_mode {
+                SP1ProofMode::Groth16 | SP1ProofMode::Plonk => proof.bytes(),
+                SP1ProofMode::Core | SP1ProofMode::Compressed => {
+                    bincode::serialize(&proof.proof).unwrap()
+                }
+            };
 
             hooks
                 .on_proving_end(
-                    client_input.current_block.number,
+                    block_number,
+                    block_hash,
+                    &proof,
                     &proof_bytes,
                     self.vk().as_ref(),
                     cycle_count,
                     proving_duration,
                 )
                 .await?;
-
-            info!("Proof successfully generated!");
         }
 
-        Ok(())
+        Ok(block_hash)
     }
 }
 
@@ -147,10 +231,10 @@ where
     C: ExecutorComponents,
     P: Provider<C::Network> + Clone,
 {
-    async fn execute(&self, block_number: u64) -> eyre::Result<()> {
+    async fn execute(&self, block_id: BlockId) -> eyre::Result<B256> {
         match self {
-            Either::Left(ref executor) => executor.execute(block_number).await,
-            Either::Right(ref executor) => executor.execute(block_number).await,
+            Either::Left(ref executor) => executor.execute(block_id).await,
+            Either::Right(ref executor) => executor.execute(block_id).await,
         }
     }
 
@@ -224,7 +308,8 @@ where
             host_executor: HostExecutor::new(
                 evm_config,
                 Arc::new(C::try_into_chain_spec(&config.genesis)?),
-            ),
+            )
+            .with_witness_minimization(config.minimize_witness),
             client,
             pk: Arc::new(pk),
             vk: Arc::new(vk),
@@ -241,6 +326,17 @@ where
         }
         Ok(())
     }
+
+    /// Returns whether the block with the given hash is still part of the canonical chain.
+    pub async fn is_canonical(&self, block_number: u64, block_hash: B256) -> eyre::Result<bool> {
+        let block = self
+            .provider
+            .get_block_by_number(block_number.into())
+            .await?
+            .ok_or(HostError::ExpectedBlock(block_number))?;
+
+        Ok(block.header().hash() == block_hash)
+    }
 }
 
 impl<C, P> BlockExecutor<C> for FullExecutor<C, P>
@@ -248,14 +344,22 @@ where
     C: ExecutorComponents,
     P: Provider<C::Network> + Clone,
 {
-    async fn execute(&self, block_number: u64) -> eyre::Result<()> {
+    async fn execute(&self, block_id: BlockId) -> eyre::Result<B256> {
+        // Resolve the block id first, so that a tag designates the same block during the whole
+        // execution.
+        let block =
+            self.provider.get_block(block_id).await?.ok_or(HostError::ExpectedBlockId(block_id))?;
+        let block_number = block.header().number();
+        let block_hash = block.header().hash();
+
         self.hooks.on_execution_start(block_number).await?;
 
         let client_input_from_cache = self.config.cache_dir.as_ref().and_then(|cache_dir| {
             match try_load_input_from_cache::<C::Primitives>(
                 cache_dir,
                 self.config.chain.id(),
-                block_number,
+                Some(block_number),
+                Some(block_hash),
             ) {
                 Ok(client_input) => client_input,
                 Err(e) => {
@@ -276,7 +380,7 @@ where
                 let client_input = self
                     .host_executor
                     .execute(
-                        block_number,
+                        block_hash.into(),
                         &self.provider,
                         self.config.genesis.clone(),
                         self.config.custom_beneficiary,
@@ -285,24 +389,20 @@ where
                     .await?;
 
                 if let Some(ref cache_dir) = self.config.cache_dir {
-                    let input_folder = cache_dir.join(format!("input/{}", self.config.chain.id()));
-                    if !input_folder.exists() {
-                        std::fs::create_dir_all(&input_folder)?;
-                    }
-
-                    let input_path = input_folder.join(format!("{}.bin", block_number));
-                    let mut cache_file = std::fs::File::create(input_path)?;
-
-                    bincode::serialize_into(&mut cache_file, &client_input)?;
+                    save_input_to_cache(
+                        cache_dir,
+                        self.config.chain.id(),
+                        block_number,
+                        block_hash,
+                        &client_input,
+                    )?;
                 }
 
                 client_input
             }
         };
 
-        self.process_client(client_input, &self.hooks).await?;
-
-        Ok(())
+        self.process_client(client_input, &self.hooks).await
     }
 
     fn client(&self) -> Arc<C::Prover> {
@@ -372,11 +472,18 @@ impl<C> BlockExecutor<C> for CachedExecutor<C>
 where
     C: ExecutorComponents,
 {
-    async fn execute(&self, block_number: u64) -> eyre::Result<()> {
+    async fn execute(&self, block_id: BlockId) -> eyre::Result<B256> {
+        let (block_number, block_hash) = match block_id {
+            BlockId::Number(BlockNumberOrTag::Number(block_number)) => (Some(block_number), None),
+            BlockId::Hash(block_hash) => (None, Some(block_hash.block_hash)),
+            BlockId::Number(tag) => bail!("Executing the {tag} block requires a RPC URL"),
+        };
+
         let client_input = try_load_input_from_cache::<C::Primitives>(
             &self.cache_dir,
             self.config.chain.id(),
             block_number,
+            block_hash,
         )?
         .ok_or(eyre::eyre!("No cached input found"))?;
 
@@ -410,7 +517,7 @@ where
 }
 
 // Block execution in SP1 is a long-running, blocking task, so run it in a separate thread.
-async fn execute_client<P: Prover<CpuProverComponents> + 'static>(
+async fn execute_client<P: BlockProver>(
     number: u64,
     client: Arc<P>,
     pk: Arc<SP1ProvingKey>,
@@ -419,27 +526,283 @@ async fn execute_client<P: Prover<CpuProverComponents> + 'static>(
     task::spawn_blocking(move || {
         info_span!("execute_client", number).in_scope(|| {
             let result = client.execute(&pk.elf, &stdin);
-            result.map_err(|err| eyre::eyre!("{err}"))
+            result
+                .map_err(|err| eyre::Report::new(HostError::ClientExecutionFailed(err.to_string())))
         })
     })
     .await
     .map_err(|err| eyre::eyre!("{err}"))
 }
 
+/// Returns the proof of the block held by the cache and its metadata, if it's valid and was
+/// generated in the same mode for the same program.
+async fn load_cached_proof<P: BlockProver>(
+    cache_dir: PathBuf,
+    chain_id: u64,
+    block_hash: B256,
+    prove_mode: SP1ProofMode,
+    client: Arc<P>,
+    vk: Arc<SP1VerifyingKey>,
+) -> Option<(SP1ProofWithPublicValues, ProofMetadata)> {
+    let result = task::spawn_blocking(move || -> eyre::Result<_> {
+        let Some((proof, metadata)) = try_load_proof_from_cache(&cache_dir, chain_id, block_hash)?
+        else {
+            return Ok(None);
+        };
+
+        if metadata.block_hash != block_hash ||
+            metadata.vk_hash != B256::from(vk.bytes32_raw()) ||
+            metadata.proof_mode != proof_mode_name(prove_mode)
+        {
+            return Ok(None);
+        }
+
+        verify_proof(client.as_ref(), vk.as_ref(), &proof, block_hash)?;
+
+        Ok(Some((proof, metadata)))
+    })
+    .await
+    .map_err(|err| eyre::eyre!("{err}"));
+
+    match result {
+        Ok(Ok(cached_proof)) => cached_proof,
+        Ok(Err(err)) | Err(err) => {
+            warn!("Failed to load proof from cache: {}", err);
+            None
+        }
+    }
+}
+
+/// Verifies a proof against the verifying key, and checks that it commits to the expected block.
+fn verify_proof<P: BlockProver>(
+    client: &P,
+    vk: &SP1VerifyingKey,
+    proof: &SP1ProofWithPublicValues,
+    block_hash: B256,
+) -> Result<(), HostError> {
+    client.verify(proof, vk).map_err(|err| HostError::ProofVerificationFailed(err.to_string()))?;
+
+    let committed_block_hash =
+        proof.public_values.clone().read::<CommittedHeader>().header.hash_slow();
+
+    if committed_block_hash != block_hash {
+        return Err(HostError::ProofVerificationFailed(format!(
+            "the proof commits to block {committed_block_hash}, expected {block_hash}"
+        )));
+    }
+
+    Ok(())
+}
+
+/// Loads the client input from the cache, looking it up by hash first then by number.
+///
+/// If the hash is provided, a cached input is only returned if it was generated for the block
+/// with the given hash.
 fn try_load_input_from_cache<P: NodePrimitives + DeserializeOwned>(
     cache_dir: &Path,
     chain_id: u64,
-    block_number: u64,
+    block_number: Option<u64>,
+    block_hash: Option<B256>,
+) -> eyre::Result<Option<ClientExecutorInput<P>>> {
+    let input_folder = cache_dir.join(format!("input/{}", chain_id));
+
+    if let Some(block_hash) = block_hash {
+        let cache_path = input_folder.join(format!("{}.bin", block_hash));
+
+        if let Some(client_input) = load_cached_input(&cache_path, Some(block_hash))? {
+            return Ok(Some(client_input));
+        }
+    }
+
+    let Some(block_number) = block_number else {
+        return Ok(None);
+    };
+
+    load_cached_input(&input_folder.join(format!("{}.bin", block_number)), block_hash)
+}
+
+/// Loads a cached client input, if it exists and was generated for the block with the given hash.
+fn load_cached_input<P: NodePrimitives + DeserializeOwned>(
+    cache_path: &Path,
+    block_hash: Option<B256>,
 ) -> eyre::Result<Option<ClientExecutorInput<P>>> {
-    let cache_path = cache_dir.join(format!("input/{}/{}.bin", chain_id, block_number));
+    if !cache_path.exists() {
+        return Ok(None);
+    }
+
+    // TODO: prune the cache if invalid instead
+    let mut cache_file = std::fs::File::open(cache_path)?;
+    let client_input: ClientExecutorInput<P> = bincode::deserialize_from(&mut cache_file)?;
+
+    // The cached input may have been generated for a block that has been reorged since.
+    if let Some(block_hash) = block_hash {
+        let cached_block_hash = client_input.current_block.header.hash_slow();
+
+        if cached_block_hash != block_hash {
+            warn!(?cached_block_hash, ?block_hash, "Cached input is for another block");
+            return Ok(None);
+        }
+    }
 
-    if cache_path.exists() {
-        // TODO: prune the cache if invalid instead
-        let mut cache_file = std::fs::File::open(cache_path)?;
-        let client_input = bincode::deserialize_from(&mut cache_file)?;
+    Ok(Some(client_input))
+}
+
+/// Saves the client input to the cache, keyed by both its number and its hash.
+fn save_input_to_cache<P: NodePrimitives>(
+    cache_dir: &Path,
+    chain_id: u64,
+    block_number: u64,
+    block_hash: B256,
+    client_input: &ClientExecutorInput<P>,
+) -> eyre::Result<()>
+where
+    ClientExecutorInput<P>: Serialize,
+{
+    let input_folder = cache_dir.join(format!("input/{}", chain_id));
+    if !input_folder.exists() {
+        std::fs::create_dir_all(&input_folder)?;
+    }
+
+    // The files are written aside then renamed into place, so that the input cached for a
+    // reorged block with the same number is replaced rather than overwritten, and readers never
+    // see a partially written input.
+    let hash_path = input_folder.join(format!("{}.bin", block_hash));
+    let tmp_path = input_folder.join(format!("{}.bin.tmp", block_hash));
+    let mut cache_file = std::fs::File::create(&tmp_path)?;
+
+    bincode::serialize_into(&mut cache_file, client_input)?;
+    std::fs::rename(&tmp_path, &hash_path)?;
+
+    // Link the input by number as well, and fall back to a copy if the filesystem doesn't
+    // support hard links.
+    if std::fs::hard_link(&hash_path, &tmp_path).is_err() {
+        std::fs::copy(&hash_path, &tmp_path)?;
+    }
+    std::fs::rename(&tmp_path, input_folder.join(format!("{}.bin", block_number)))?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy_consensus::{Block, Header};
+    use alloy_primitives::Bytes;
+    use reth_ethereum_primitives::EthPrimitives;
+    use rsp_mpt::{EthereumState, MptNode};
+    use rsp_primitives::genesis::Genesis;
+
+    use super::*;
+    use crate::{test_utils::MockProver, EthExecutorComponents};
+
+    type MockExecutor = CachedExecutor<EthExecutorComponents<(), MockProver>>;
+
+    async fn mock_executor(prover: MockProver, cache_dir: &Path) -> MockExecutor {
+        let config = Config {
+            cache_dir: Some(cache_dir.to_path_buf()),
+            prove_mode: Some(SP1ProofMode::Core),
+            verify_proof: true,
+            ..Config::mainnet()
+        };
+
+        CachedExecutor::try_new(
+            b"elf".to_vec(),
+            Arc::new(prover),
+            (),
+            cache_dir.to_path_buf(),
+            config,
+        )
+        .await
+        .unwrap()
+    }
+
+    fn input(extra_data: &[u8]) -> ClientExecutorInput<EthPrimitives> {
+        let header = Header {
+            number: 1,
+            extra_data: Bytes::copy_from_slice(extra_data),
+            ..Default::default()
+        };
+
+        ClientExecutorInput {
+            current_block: Block { header, body: Default::default() },
+            ancestor_headers: vec![Header::default()],
+            parent_state: EthereumState {
+                state_trie: MptNode::default(),
+                storage_tries: Default::default(),
+            },
+            bytecodes: vec![],
+            genesis: Genesis::Mainnet,
+            custom_beneficiary: None,
+            opcode_tracking: false,
+        }
+    }
+
+    #[test]
+    fn test_input_cache_keeps_reorged_inputs() {
+        let cache_dir =
+            std::env::temp_dir().join(format!("rsp-input-cache-{}", std::process::id()));
+        let input_folder = cache_dir.join("input/1");
+        let (orphaned, canonical) = (input(b"orphaned"), input(b"canonical"));
+        let orphaned_hash = orphaned.current_block.header.hash_slow();
+        let canonical_hash = canonical.current_block.header.hash_slow();
+
+        save_input_to_cache(&cache_dir, 1, 1, orphaned_hash, &orphaned).unwrap();
+        save_input_to_cache(&cache_dir, 1, 1, canonical_hash, &canonical).unwrap();
+
+        // The input of the orphaned block is left untouched.
+        let load = |number, hash| {
+            try_load_input_from_cache::<EthPrimitives>(&cache_dir, 1, number, hash)
+                .unwrap()
+                .map(|input| input.current_block.header.hash_slow())
+        };
+        assert_eq!(load(None, Some(orphaned_hash)), Some(orphaned_hash));
+        assert_eq!(load(Some(1), Some(canonical_hash)), Some(canonical_hash));
+        assert_eq!(load(Some(1), None), Some(canonical_hash));
+
+        // An input found under the wrong hash is ignored.
+        std::fs::copy(
+            input_folder.join(format!("{canonical_hash}.bin")),
+            input_folder.join(format!("{orphaned_hash}.bin")),
+        )
+        .unwrap();
+        assert_eq!(load(Some(1), Some(orphaned_hash)), None);
+
+        std::fs::remove_dir_all(cache_dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_process_client_reuses_cached_proofs() {
+        let cache_dir =
+            std::env::temp_dir().join(format!("rsp-proof-cache-{}", std::process::id()));
+        let input = input(b"block");
+        let block_hash = input.current_block.header.hash_slow();
+
+        let executor = mock_executor(MockProver::default(), &cache_dir).await;
+        assert_eq!(executor.process_client(input.clone(), &()).await.unwrap(), block_hash);
+        assert_eq!(executor.client().proof_count(), 1);
+
+        // The proof is cached by hash, and no temporary file is left behind.
+        let cached_files = std::fs::read_dir(cache_dir.join("proofs/1"))
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+            .collect::<std::collections::BTreeSet<_>>();
+        assert_eq!(
+            cached_files,
+            [format!("{block_hash}.bin"), format!("{block_hash}.json")].into_iter().collect()
+        );
+
+        // The cached proof is verified and reused.
+        assert_eq!(executor.process_client(input.clone(), &()).await.unwrap(), block_hash);
+        assert_eq!(executor.client().proof_count(), 1);
+
+        // A cached proof failing verification is generated again, and verified as well.
+        let executor = mock_executor(MockProver::rejecting_proofs(), &cache_dir).await;
+        let err = executor.process_client(input, &()).await.unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<HostError>(),
+            Some(HostError::ProofVerificationFailed(_))
+        ));
+        assert_eq!(executor.client().proof_count(), 1);
 
-        Ok(Some(client_input))
-    } else {
-        Ok(None)
+        std::fs::remove_dir_all(cache_dir).unwrap();
     }
 }
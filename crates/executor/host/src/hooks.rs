@@ -1,8 +1,9 @@
 use std::{future::Future, time::Duration};
 
 use alloy_consensus::Block;
+use alloy_primitives::B256;
 use reth_primitives_traits::NodePrimitives;
-use sp1_sdk::{ExecutionReport, SP1VerifyingKey};
+use sp1_sdk::{ExecutionReport, SP1ProofWithPublicValues, SP1VerifyingKey};
 
 pub trait ExecutionHooks: Send {
     fn on_execution_start(
@@ -27,6 +28,8 @@ pub trait ExecutionHooks: Send {
     fn on_proving_end(
         &self,
         _block_number: u64,
+        _block_hash: B256,
+        _proof: &SP1ProofWithPublicValues,
         _proof_bytes: &[u8],
         _vk: &SP1VerifyingKey,
         _cycle_count: Option<u64>,
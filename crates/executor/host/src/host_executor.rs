@@ -4,6 +4,7 @@ use alloy_consensus::{BlockHeader, Header, TxReceipt};
 use alloy_network::BlockResponse;
 use alloy_primitives::{Bloom, Sealable};
 use alloy_provider::{Network, Provider};
+use alloy_rpc_types::BlockId;
 use reth_chainspec::ChainSpec;
 use reth_evm::{
     execute::{BasicBlockExecutor, Executor},
@@ -12,17 +13,20 @@ use reth_evm::{
 use reth_evm_ethereum::EthEvmConfig;
 use reth_optimism_chainspec::OpChainSpec;
 use reth_optimism_evm::OpEvmConfig;
-use reth_primitives_traits::{Block, BlockBody, SealedHeader};
+use reth_primitives_traits::{Block, BlockBody, NodePrimitives, RecoveredBlock, SealedHeader};
 use reth_trie::{HashedPostState, KeccakKeyHasher};
-use revm::database::CacheDB;
+use revm::database::{BundleState, CacheDB, WrapDatabaseRef};
 use revm_primitives::Address;
 use rsp_client_executor::{
-    custom::CustomEvmFactory, io::ClientExecutorInput, BlockValidator, IntoInput, IntoPrimitives,
+    custom::CustomEvmFactory,
+    io::{ClientExecutorInput, WitnessInput},
+    BlockValidator, IntoInput, IntoPrimitives,
 };
+use rsp_mpt::FlatWitness;
 use rsp_primitives::genesis::Genesis;
 use rsp_rpc_db::RpcDb;
 
-use crate::HostError;
+use crate::{checking_db::CheckingDb, HostError};
 
 pub type EthHostExecutor = HostExecutor<EthEvmConfig<ChainSpec, CustomEvmFactory>, ChainSpec>;
 
@@ -33,6 +37,8 @@ pub type OpHostExecutor = HostExecutor<OpEvmConfig, OpChainSpec>;
 pub struct HostExecutor<C: ConfigureEvm, CS> {
     evm_config: C,
     chain_spec: Arc<CS>,
+    /// Whether to prune the nodes that the client does not need from the client input.
+    witness_minimization: bool,
 }
 
 impl EthHostExecutor {
@@ -43,26 +49,38 @@ impl EthHostExecutor {
                 CustomEvmFactory::new(custom_beneficiary),
             ),
             chain_spec,
+            witness_minimization: true,
         }
     }
 }
 
 impl OpHostExecutor {
     pub fn optimism(chain_spec: Arc<OpChainSpec>) -> Self {
-        Self { evm_config: OpEvmConfig::optimism(chain_spec.clone()), chain_spec }
+        Self {
+            evm_config: OpEvmConfig::optimism(chain_spec.clone()),
+            chain_spec,
+            witness_minimization: true,
+        }
     }
 }
 
 impl<C: ConfigureEvm, CS> HostExecutor<C, CS> {
     /// Creates a new [HostExecutor].
     pub fn new(evm_config: C, chain_spec: Arc<CS>) -> Self {
-        Self { evm_config, chain_spec }
+        Self { evm_config, chain_spec, witness_minimization: true }
     }
 
-    /// Executes the block with the given block number.
+    /// Enables or disables the minimization of the client input, enabled by default. The client
+    /// input is still checked against a re-execution of the block when disabled.
+    pub fn with_witness_minimization(mut self, witness_minimization: bool) -> Self {
+        self.witness_minimization = witness_minimization;
+        self
+    }
+
+    /// Executes the block with the given block id, that can be a number, a hash or a tag.
     pub async fn execute<P, N>(
         &self,
-        block_number: u64,
+        block_id: BlockId,
         provider: &P,
         genesis: Genesis,
         custom_beneficiary: Option<Address>,
@@ -76,18 +94,21 @@ impl<C: ConfigureEvm, CS> HostExecutor<C, CS> {
         // Fetch the current block and the previous block from the provider.
         tracing::info!("fetching the current block and the previous block");
         let rpc_block = provider
-            .get_block_by_number(block_number.into())
+            .get_block(block_id)
             .full()
             .await?
-            .ok_or(HostError::ExpectedBlock(block_number))?;
+            .ok_or(HostError::ExpectedBlockId(block_id))?;
 
         let current_block = C::Primitives::into_primitive_block(rpc_block.clone());
+        let block_number = current_block.header().number();
 
+        // Fetch the parent by hash, so both blocks belong to the same chain even if a reorg
+        // happened in between the two requests.
         let previous_block = provider
-            .get_block_by_number((block_number - 1).into())
+            .get_block_by_hash(current_block.header().parent_hash())
             .full()
             .await?
-            .ok_or(HostError::ExpectedBlock(block_number))
+            .ok_or(HostError::ExpectedBlock(block_number - 1))
             .map(C::Primitives::into_primitive_block)?;
 
         // Setup the database for the block executor.
@@ -154,9 +175,9 @@ impl<C: ConfigureEvm, CS> HostExecutor<C, CS> {
         tracing::info!("verifying the state root");
         let state_root = {
             let mut mutated_state = state.clone();
-            mutated_state.update(&HashedPostState::from_bundle_state::<KeccakKeyHasher>(
+            mutated_state.par_update(&HashedPostState::from_bundle_state::<KeccakKeyHasher>(
                 &execution_output.state.state,
-            ));
+            ))?;
             mutated_state.state_root()
         };
         if state_root != current_block.header().state_root() {
@@ -211,7 +232,7 @@ impl<C: ConfigureEvm, CS> HostExecutor<C, CS> {
         );
 
         // Create the client input.
-        let client_input = ClientExecutorInput {
+        let mut client_input = ClientExecutorInput {
             current_block: C::Primitives::into_input_block(current_block),
             ancestor_headers,
             parent_state: state,
@@ -220,8 +241,77 @@ impl<C: ConfigureEvm, CS> HostExecutor<C, CS> {
             custom_beneficiary,
             opcode_tracking,
         };
+
+        // Re-execute the block against the client input, so a witness missing some data fails
+        // here rather than in the zkVM, and prune the nodes that the client does not need.
+        if self.witness_minimization {
+            tracing::info!("checking and minimizing the client input");
+            self.minimize_witness(&mut client_input, &block)?;
+        } else {
+            tracing::info!("checking the client input");
+            self.check_witness(&client_input, &block)?;
+        }
+
         tracing::info!("successfully generated client input");
 
         Ok(client_input)
     }
+
+    /// Prunes the parent state of the input down to the trie nodes that the client decodes to
+    /// execute the block and update the state, and replaces the other nodes by digests.
+    fn minimize_witness(
+        &self,
+        input: &mut ClientExecutorInput<C::Primitives>,
+        block: &RecoveredBlock<<C::Primitives as NodePrimitives>::Block>,
+    ) -> Result<(), HostError> {
+        let mut witness = FlatWitness::from(&input.parent_state);
+        let (nodes_count, nodes_len) = (witness.nodes.len(), witness.nodes_len());
+
+        witness.minimize(|state| {
+            input.parent_state = state;
+            let bundle_state = self.check_witness(input, block)?;
+            let post_state =
+                HashedPostState::from_bundle_state::<KeccakKeyHasher>(&bundle_state.state);
+
+            // the client may apply the updates in another order, so keep the nodes that any
+            // order of the deletions needs
+            input.parent_state.decode_deleted_siblings(&post_state);
+            input.parent_state.update(&post_state)?;
+
+            Ok::<_, HostError>(())
+        })??;
+
+        tracing::info!(
+            "minimized the client input: nodes={} -> {}, bytes={} -> {}",
+            nodes_count,
+            witness.nodes.len(),
+            nodes_len,
+            witness.nodes_len()
+        );
+        input.parent_state = witness.into_state()?;
+
+        Ok(())
+    }
+
+    /// Executes the block against the same database as the client, reports all the data that
+    /// cannot be resolved from the client input, and returns the resulting bundle state.
+    fn check_witness(
+        &self,
+        input: &ClientExecutorInput<C::Primitives>,
+        block: &RecoveredBlock<<C::Primitives as NodePrimitives>::Block>,
+    ) -> Result<BundleState, HostError> {
+        let sealed_headers = input.sealed_headers().collect::<Vec<_>>();
+        let checking_db = CheckingDb::new(input.witness_db(&sealed_headers)?);
+
+        let execution_output =
+            BasicBlockExecutor::new(self.evm_config.clone(), WrapDatabaseRef(&checking_db))
+                .execute(block);
+
+        let missing = checking_db.into_missing();
+        if !missing.is_empty() {
+            return Err(HostError::IncompleteWitness(missing));
+        }
+
+        Ok(execution_output?.state)
+    }
 }
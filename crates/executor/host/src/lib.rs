@@ -8,18 +8,29 @@ use reth_optimism_chainspec::OpChainSpec;
 use reth_optimism_evm::OpEvmConfig;
 use revm_primitives::Address;
 use rsp_client_executor::custom::CustomEvmFactory;
-use rsp_primitives::genesis::Genesis;
+use rsp_primitives::genesis::{genesis_from_json, Genesis};
 use sp1_sdk::SP1ProofMode;
-use std::{path::PathBuf, sync::Arc};
+use std::{
+    path::{Path, PathBuf},
+    sync::Arc,
+};
 use url::Url;
 
 #[cfg(feature = "alerting")]
 pub mod alerting;
 
+#[cfg(feature = "remote-prover")]
+pub mod remote_prover;
+
+mod checking_db;
+pub use checking_db::MissingWitness;
+
 mod error;
 
 mod executor_components;
-pub use executor_components::{EthExecutorComponents, ExecutorComponents, OpExecutorComponents};
+pub use executor_components::{
+    BlockProver, EthExecutorComponents, ExecutorComponents, OpExecutorComponents,
+};
 
 mod full_executor;
 pub use full_executor::{build_executor, BlockExecutor, EitherExecutor, FullExecutor};
@@ -30,6 +41,15 @@ pub use hooks::ExecutionHooks;
 mod host_executor;
 pub use host_executor::{EthHostExecutor, HostExecutor, OpHostExecutor};
 
+mod proof_cache;
+pub use proof_cache::ProofMetadata;
+
+mod proof_mode;
+pub use proof_mode::{parse_proof_mode, proof_mode_name};
+
+#[cfg(test)]
+mod test_utils;
+
 pub fn create_eth_block_execution_strategy_factory(
     genesis: &Genesis,
     custom_beneficiary: Option<Address>,
@@ -39,6 +59,13 @@ pub fn create_eth_block_execution_strategy_factory(
     EthEvmConfig::new_with_evm_factory(chain_spec, CustomEvmFactory::new(custom_beneficiary))
 }
 
+/// Reads the genesis of a custom chain from a JSON genesis file.
+pub fn genesis_from_path(path: &Path) -> eyre::Result<Genesis> {
+    let genesis_json = std::fs::read_to_string(path).map_err(HostError::FailedToReadGenesisFile)?;
+
+    Ok(Genesis::Custom(genesis_from_json(&genesis_json)?.config))
+}
+
 pub fn create_op_block_execution_strategy_factory(genesis: &Genesis) -> OpEvmConfig {
     let chain_spec: Arc<OpChainSpec> = Arc::new(genesis.try_into().unwrap());
 
@@ -54,6 +81,10 @@ pub struct Config {
     pub prove_mode: Option<SP1ProofMode>,
     pub skip_client_execution: bool,
     pub opcode_tracking: bool,
+    /// Whether to verify the generated proofs before handing them to the hooks.
+    pub verify_proof: bool,
+    /// Whether to prune the trie nodes that the client does not need from the client input.
+    pub minimize_witness: bool,
 }
 
 impl Config {
@@ -67,6 +98,8 @@ impl Config {
             prove_mode: None,
             skip_client_execution: false,
             opcode_tracking: false,
+            verify_proof: false,
+            minimize_witness: true,
         }
     }
 }
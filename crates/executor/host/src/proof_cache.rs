@@ -0,0 +1,69 @@
+use std::{fs::File, path::Path};
+
+use alloy_primitives::B256;
+use serde::{Deserialize, Serialize};
+use sp1_sdk::SP1ProofWithPublicValues;
+
+/// The metadata of a proof saved to the cache, written next to it as JSON.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProofMetadata {
+    /// The hash of the proven block.
+    pub block_hash: B256,
+    /// The hash of the verifying key of the program.
+    pub vk_hash: B256,
+    /// The number of cycles, if reported by the prover.
+    pub cycle_count: Option<u64>,
+    /// The proving duration, in milliseconds.
+    pub proving_duration_ms: u64,
+    /// The proof mode, e.g. `compressed`.
+    pub proof_mode: String,
+}
+
+/// Loads the proof of the block with the given hash and its metadata from the cache, if any.
+pub(crate) fn try_load_proof_from_cache(
+    cache_dir: &Path,
+    chain_id: u64,
+    block_hash: B256,
+) -> eyre::Result<Option<(SP1ProofWithPublicValues, ProofMetadata)>> {
+    let proof_folder = cache_dir.join(format!("proofs/{}", chain_id));
+    let proof_path = proof_folder.join(format!("{}.bin", block_hash));
+    let metadata_path = proof_folder.join(format!("{}.json", block_hash));
+
+    if !proof_path.exists() || !metadata_path.exists() {
+        return Ok(None);
+    }
+
+    let metadata = serde_json::from_reader(File::open(metadata_path)?)?;
+    let proof = bincode::deserialize_from(File::open(proof_path)?)?;
+
+    Ok(Some((proof, metadata)))
+}
+
+/// Saves the proof of a block to the cache, in the `SP1ProofWithPublicValues::save` format, with
+/// its metadata, both keyed by the block hash.
+pub(crate) fn save_proof_to_cache(
+    cache_dir: &Path,
+    chain_id: u64,
+    proof: &SP1ProofWithPublicValues,
+    metadata: &ProofMetadata,
+) -> eyre::Result<()> {
+    let proof_folder = cache_dir.join(format!("proofs/{}", chain_id));
+    if !proof_folder.exists() {
+        std::fs::create_dir_all(&proof_folder)?;
+    }
+
+    // The files are written aside then renamed into place, so that readers never see a partially
+    // written proof. The metadata is renamed last, so that a proof is only picked up once both
+    // files are in place.
+    let proof_path = proof_folder.join(format!("{}.bin", metadata.block_hash));
+    let tmp_path = proof_folder.join(format!("{}.bin.tmp", metadata.block_hash));
+    bincode::serialize_into(File::create(&tmp_path)?, proof)?;
+    std::fs::rename(&tmp_path, proof_path)?;
+
+    let metadata_path = proof_folder.join(format!("{}.json", metadata.block_hash));
+    let tmp_path = proof_folder.join(format!("{}.json.tmp", metadata.block_hash));
+    serde_json::to_writer_pretty(File::create(&tmp_path)?, metadata)?;
+    std::fs::rename(&tmp_path, metadata_path)?;
+
+    Ok(())
+}
@@ -0,0 +1,38 @@
+use sp1_sdk::SP1ProofMode;
+
+/// Returns the name of a proof mode, as accepted by [`parse_proof_mode`].
+pub fn proof_mode_name(mode: SP1ProofMode) -> &'static str {
+    match mode {
+        SP1ProofMode::Core => "core",
+        SP1ProofMode::Compressed => "compressed",
+        SP1ProofMode::Plonk => "plonk",
+        SP1ProofMode::Groth16 => "groth16",
+    }
+}
+
+/// Parses a proof mode from its name, e.g. `groth16`.
+///
+/// The error is a plain string so the function can be used as a clap value parser.
+pub fn parse_proof_mode(name: &str) -> Result<SP1ProofMode, String> {
+    match name {
+        "core" => Ok(SP1ProofMode::Core),
+        "compressed" => Ok(SP1ProofMode::Compressed),
+        "plonk" => Ok(SP1ProofMode::Plonk),
+        "groth16" => Ok(SP1ProofMode::Groth16),
+        _ => Err(format!("Unknown proof mode {name}, expected core, compressed, groth16 or plonk")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_proof_mode() {
+        for name in ["core", "compressed", "plonk", "groth16"] {
+            assert_eq!(parse_proof_mode(name).map(proof_mode_name), Ok(name));
+        }
+
+        assert!(parse_proof_mode("stark").is_err());
+    }
+}
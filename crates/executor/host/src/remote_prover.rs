@@ -0,0 +1,299 @@
+//! A prover offloading the proof generation to a remote proving service.
+//!
+//! The service exposes two endpoints:
+//! - `POST {endpoint}/jobs` submits a [`ProvingJobRequest`], and answers a [`ProvingJob`] with the
+//!   job ID.
+//! - `GET {endpoint}/jobs/{id}` answers the [`ProvingJobStatus`] of the job, polled until it is
+//!   completed or failed.
+
+use std::time::{Duration, Instant};
+
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use eyre::bail;
+use serde::{Deserialize, Serialize};
+use sp1_sdk::{
+    CpuProver, ExecutionReport, SP1ProofMode, SP1ProofWithPublicValues, SP1ProvingKey,
+    SP1PublicValues, SP1Stdin, SP1VerifyingKey,
+};
+use tokio::{runtime::Handle, time::sleep};
+use tracing::info;
+use url::Url;
+
+use crate::{proof_mode_name, BlockProver, HostError};
+
+const DEFAULT_POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(3600);
+
+/// A proving job submitted to the service.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProvingJobRequest {
+    /// The program ELF, base64 encoded.
+    pub elf: String,
+    /// The bincode serialized stdin, base64 encoded.
+    pub stdin: String,
+    /// The proof mode, e.g. `compressed`.
+    pub mode: String,
+}
+
+/// A proving job accepted by the service.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProvingJob {
+    pub id: String,
+}
+
+/// The status of a proving job.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "status", rename_all = "lowercase")]
+pub enum ProvingJobStatus {
+    Pending,
+    Running,
+    Completed {
+        /// The bincode serialized `SP1ProofWithPublicValues`, base64 encoded.
+        proof: String,
+        cycles: Option<u64>,
+    },
+    Failed {
+        error: String,
+    },
+}
+
+/// A [`BlockProver`] submitting the proofs to a remote proving service.
+///
+/// The programs are set up, executed and verified by the given local prover, only the proof
+/// generation is offloaded.
+pub struct RemoteProver<P = CpuProver> {
+    endpoint: Url,
+    http_client: reqwest::Client,
+    local_prover: P,
+    poll_interval: Duration,
+    timeout: Duration,
+}
+
+impl<P: BlockProver> RemoteProver<P> {
+    pub fn new(mut endpoint: Url, local_prover: P) -> Self {
+        // Without a trailing slash, `Url::join` would replace the last path segment.
+        if !endpoint.path().ends_with('/') {
+            endpoint.set_path(&format!("{}/", endpoint.path()));
+        }
+
+        Self {
+            endpoint,
+            http_client: reqwest::Client::new(),
+            local_prover,
+            poll_interval: DEFAULT_POLL_INTERVAL,
+            timeout: DEFAULT_TIMEOUT,
+        }
+    }
+
+    /// Overrides the interval between two status requests.
+    pub fn with_poll_interval(mut self, poll_interval: Duration) -> Self {
+        self.poll_interval = poll_interval;
+        self
+    }
+
+    /// Overrides how long a proving job is waited for before giving up on it.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Submits a proving job, and waits for its completion.
+    ///
+    /// Fails with [`HostError::RemoteProvingTimedOut`] if the job doesn't complete in time.
+    pub async fn prove_remote(
+        &self,
+        elf: &[u8],
+        stdin: &SP1Stdin,
+        mode: SP1ProofMode,
+    ) -> eyre::Result<(SP1ProofWithPublicValues, Option<u64>)> {
+        let request = ProvingJobRequest {
+            elf: BASE64.encode(elf),
+            stdin: BASE64.encode(bincode::serialize(stdin)?),
+            mode: proof_mode_name(mode).to_string(),
+        };
+
+        let job: ProvingJob = self
+            .http_client
+            .post(self.endpoint.join("jobs")?)
+            .json(&request)
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+
+        info!(job_id = %job.id, "Proving job submitted");
+
+        let status_url = self.endpoint.join(&format!("jobs/{}", job.id))?;
+        let started_at = Instant::now();
+
+        loop {
+            let status: ProvingJobStatus = self
+                .http_client
+                .get(status_url.clone())
+                .send()
+                .await?
+                .error_for_status()?
+                .json()
+                .await?;
+
+            match status {
+                ProvingJobStatus::Pending | ProvingJobStatus::Running => {
+                    if started_at.elapsed() >= self.timeout {
+                        return Err(HostError::RemoteProvingTimedOut(job.id, self.timeout).into());
+                    }
+
+                    sleep(self.poll_interval).await
+                }
+                ProvingJobStatus::Completed { proof, cycles } => {
+                    let proof = bincode::deserialize(&BASE64.decode(proof)?)?;
+
+                    return Ok((proof, cycles));
+                }
+                ProvingJobStatus::Failed { error } => {
+                    bail!("Proving job {} failed: {error}", job.id)
+                }
+            }
+        }
+    }
+}
+
+impl<P: BlockProver> BlockProver for RemoteProver<P> {
+    fn setup(&self, elf: &[u8]) -> (SP1ProvingKey, SP1VerifyingKey) {
+        self.local_prover.setup(elf)
+    }
+
+    fn execute(
+        &self,
+        elf: &[u8],
+        stdin: &SP1Stdin,
+    ) -> Result<(SP1PublicValues, ExecutionReport), eyre::Error> {
+        self.local_prover.execute(elf, stdin)
+    }
+
+    fn prove_with_cycles(
+        &self,
+        pk: &SP1ProvingKey,
+        stdin: &SP1Stdin,
+        mode: SP1ProofMode,
+    ) -> Result<(SP1ProofWithPublicValues, Option<u64>), eyre::Error> {
+        // Called from a blocking thread, so it's fine to block on the runtime.
+        Handle::current().block_on(self.prove_remote(&pk.elf, stdin, mode))
+    }
+
+    fn verify(
+        &self,
+        proof: &SP1ProofWithPublicValues,
+        vk: &SP1VerifyingKey,
+    ) -> Result<(), eyre::Error> {
+        self.local_prover.verify(proof, vk)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use sp1_sdk::SP1Proof;
+    use tokio::sync::mpsc;
+
+    use super::*;
+    use crate::test_utils::{spawn_mock_server, MockProver, MockRequest};
+
+    /// Spawns a mock proving service answering the given JSON responses in order, under the
+    /// `/prover` path.
+    async fn spawn_mock_service(
+        responses: Vec<serde_json::Value>,
+    ) -> (Url, mpsc::UnboundedReceiver<MockRequest>) {
+        let (url, rx) = spawn_mock_server(
+            responses.into_iter().map(|response| (200, response.to_string())).collect(),
+        )
+        .await;
+
+        (url.join("prover").unwrap(), rx)
+    }
+
+    #[tokio::test]
+    async fn test_completed_job() {
+        let proof = SP1ProofWithPublicValues {
+            proof: SP1Proof::Core(vec![]),
+            public_values: SP1PublicValues::from(b"public values"),
+            sp1_version: "v5.0.0".to_string(),
+            tee_proof: None,
+        };
+        let (url, mut rx) = spawn_mock_service(vec![
+            serde_json::json!({ "id": "42" }),
+            serde_json::json!({ "status": "running" }),
+            serde_json::json!({
+                "status": "completed",
+                "proof": BASE64.encode(bincode::serialize(&proof).unwrap()),
+                "cycles": 1000,
+            }),
+        ])
+        .await;
+        let prover = RemoteProver::new(url, MockProver::default())
+            .with_poll_interval(Duration::from_millis(10));
+
+        let (received_proof, cycles) =
+            prover.prove_remote(b"elf", &SP1Stdin::new(), SP1ProofMode::Core).await.unwrap();
+        assert!(matches!(received_proof.proof, SP1Proof::Core(shards) if shards.is_empty()));
+        assert_eq!(received_proof.public_values.as_slice(), b"public values");
+        assert_eq!(cycles, Some(1000));
+
+        let request = rx.recv().await.unwrap();
+        assert!(request.request_line.starts_with("POST /prover/jobs "));
+        let request: ProvingJobRequest = serde_json::from_str(&request.body).unwrap();
+        assert_eq!(request.mode, "core");
+
+        for _ in 0..2 {
+            let request = rx.recv().await.unwrap();
+            assert!(request.request_line.starts_with("GET /prover/jobs/42 "));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_failed_job() {
+        let (url, mut rx) = spawn_mock_service(vec![
+            serde_json::json!({ "id": "42" }),
+            serde_json::json!({ "status": "pending" }),
+            serde_json::json!({ "status": "running" }),
+            serde_json::json!({ "status": "failed", "error": "out of memory" }),
+        ])
+        .await;
+        let prover = RemoteProver::new(url, MockProver::default())
+            .with_poll_interval(Duration::from_millis(10));
+
+        let err =
+            prover.prove_remote(b"elf", &SP1Stdin::new(), SP1ProofMode::Groth16).await.unwrap_err();
+        assert_eq!(err.to_string(), "Proving job 42 failed: out of memory");
+
+        let request = rx.recv().await.unwrap();
+        assert!(request.request_line.starts_with("POST /prover/jobs "));
+        let request: ProvingJobRequest = serde_json::from_str(&request.body).unwrap();
+        assert_eq!(BASE64.decode(request.elf).unwrap(), b"elf");
+        assert_eq!(request.mode, "groth16");
+
+        for _ in 0..3 {
+            let request = rx.recv().await.unwrap();
+            assert!(request.request_line.starts_with("GET /prover/jobs/42 "));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_timed_out_job() {
+        let (url, _rx) = spawn_mock_service(vec![
+            serde_json::json!({ "id": "42" }),
+            serde_json::json!({ "status": "running" }),
+        ])
+        .await;
+        let prover = RemoteProver::new(url, MockProver::default())
+            .with_poll_interval(Duration::from_millis(10))
+            .with_timeout(Duration::from_millis(50));
+
+        let err =
+            prover.prove_remote(b"elf", &SP1Stdin::new(), SP1ProofMode::Core).await.unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<HostError>(),
+            Some(HostError::RemoteProvingTimedOut(job_id, _)) if job_id == "42"
+        ));
+    }
+}
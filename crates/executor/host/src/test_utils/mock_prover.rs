@@ -0,0 +1,91 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use eyre::bail;
+use reth_ethereum_primitives::EthPrimitives;
+use rsp_client_executor::io::{ClientExecutorInput, CommittedHeader};
+use sp1_sdk::{
+    ExecutionReport, SP1Proof, SP1ProofMode, SP1ProofWithPublicValues, SP1ProvingKey,
+    SP1PublicValues, SP1Stdin, SP1VerifyingKey,
+};
+
+use crate::BlockProver;
+
+/// The SP1 version of the proofs generated by the mock prover.
+const MOCK_SP1_VERSION: &str = "mock";
+
+/// An in-memory [`BlockProver`] for the Ethereum client, which commits the header of the block
+/// instead of running the program.
+#[derive(Debug, Default)]
+pub(crate) struct MockProver {
+    /// Whether the proofs fail to verify.
+    reject_proofs: bool,
+    /// The number of proofs generated.
+    proof_count: AtomicUsize,
+}
+
+impl MockProver {
+    /// Returns a prover whose proofs never verify.
+    pub(crate) fn rejecting_proofs() -> Self {
+        Self { reject_proofs: true, ..Default::default() }
+    }
+
+    /// Returns the number of proofs generated.
+    pub(crate) fn proof_count(&self) -> usize {
+        self.proof_count.load(Ordering::SeqCst)
+    }
+}
+
+impl BlockProver for MockProver {
+    fn setup(&self, elf: &[u8]) -> (SP1ProvingKey, SP1VerifyingKey) {
+        // The keys are never used by the mock, so zeroed keys without any chip do.
+        let mut pk: SP1ProvingKey = bincode::deserialize(&[0; 1024]).unwrap();
+        pk.elf = elf.to_vec();
+        let vk = pk.vk.clone();
+
+        (pk, vk)
+    }
+
+    fn execute(
+        &self,
+        _elf: &[u8],
+        stdin: &SP1Stdin,
+    ) -> Result<(SP1PublicValues, ExecutionReport), eyre::Error> {
+        let client_input: ClientExecutorInput<EthPrimitives> =
+            bincode::deserialize(&stdin.buffer[0])?;
+        let mut public_values = SP1PublicValues::new();
+        public_values.write(&CommittedHeader::from(client_input.current_block.header));
+
+        Ok((public_values, ExecutionReport::default()))
+    }
+
+    fn prove_with_cycles(
+        &self,
+        pk: &SP1ProvingKey,
+        stdin: &SP1Stdin,
+        _mode: SP1ProofMode,
+    ) -> Result<(SP1ProofWithPublicValues, Option<u64>), eyre::Error> {
+        let (public_values, _) = self.execute(&pk.elf, stdin)?;
+        self.proof_count.fetch_add(1, Ordering::SeqCst);
+
+        let proof = SP1ProofWithPublicValues {
+            proof: SP1Proof::Core(vec![]),
+            public_values,
+            sp1_version: MOCK_SP1_VERSION.to_string(),
+            tee_proof: None,
+        };
+
+        Ok((proof, Some(1000)))
+    }
+
+    fn verify(
+        &self,
+        proof: &SP1ProofWithPublicValues,
+        _vk: &SP1VerifyingKey,
+    ) -> Result<(), eyre::Error> {
+        if self.reject_proofs || proof.sp1_version != MOCK_SP1_VERSION {
+            bail!("Invalid proof")
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,82 @@
+use std::collections::VecDeque;
+
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::TcpListener,
+    sync::mpsc,
+};
+use url::Url;
+
+/// A request received by the mock HTTP server.
+#[derive(Debug)]
+pub(crate) struct MockRequest {
+    /// The request line, e.g. `POST /jobs HTTP/1.1`.
+    pub(crate) request_line: String,
+    pub(crate) body: String,
+}
+
+/// Spawns a minimal HTTP server answering the given status codes and bodies in order, the last
+/// one being repeated once they are exhausted, and forwarding the received requests to the
+/// returned channel.
+pub(crate) async fn spawn_mock_server(
+    responses: Vec<(u16, String)>,
+) -> (Url, mpsc::UnboundedReceiver<MockRequest>) {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = Url::parse(&format!("http://{}/", listener.local_addr().unwrap())).unwrap();
+    let (tx, rx) = mpsc::unbounded_channel();
+    let mut responses = VecDeque::from(responses);
+
+    tokio::spawn(async move {
+        loop {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut buffer = Vec::new();
+            let mut chunk = [0u8; 1024];
+
+            // Read until the headers and the whole body are received.
+            let request = loop {
+                let n = socket.read(&mut chunk).await.unwrap();
+                buffer.extend_from_slice(&chunk[..n]);
+                let request = String::from_utf8_lossy(&buffer).to_string();
+                if let Some((headers, body)) = request.split_once("\r\n\r\n") {
+                    let content_length = headers
+                        .lines()
+                        .find_map(|l| {
+                            l.to_lowercase()
+                                .strip_prefix("content-length:")
+                                .map(|v| v.trim().parse::<usize>().unwrap())
+                        })
+                        .unwrap_or_default();
+                    if body.len() >= content_length {
+                        let request_line = headers.lines().next().unwrap_or_default();
+                        break MockRequest {
+                            request_line: request_line.to_string(),
+                            body: body.to_string(),
+                        };
+                    }
+                }
+            };
+
+            tx.send(request).unwrap();
+
+            let (status, body) = if responses.len() > 1 {
+                responses.pop_front().unwrap()
+            } else {
+                responses.front().cloned().unwrap_or((200, String::new()))
+            };
+            socket
+                .write_all(
+                    format!(
+                        "HTTP/1.1 {status} Mock\r\nconnection: close\r\n\
+                         content-type: application/json\r\n\
+                         content-length: {}\r\n\r\n{body}",
+                        body.len()
+                    )
+                    .as_bytes(),
+                )
+                .await
+                .unwrap();
+        }
+    });
+
+    (url, rx)
+}
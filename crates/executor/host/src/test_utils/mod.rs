@@ -0,0 +1,7 @@
+mod mock_prover;
+pub(crate) use mock_prover::MockProver;
+
+#[cfg(any(feature = "alerting", feature = "remote-prover"))]
+mod mock_server;
+#[cfg(any(feature = "alerting", feature = "remote-prover"))]
+pub(crate) use mock_server::{spawn_mock_server, MockRequest};
@@ -141,7 +141,7 @@ async fn run_e2e<C, CS, N>(
 
     // Execute the host.
     let client_input = host_executor
-        .execute(block_number, &provider, genesis.clone(), custom_beneficiary, false)
+        .execute(block_number.into(), &provider, genesis.clone(), custom_beneficiary, false)
         .await
         .expect("failed to execute host");
 
@@ -3,7 +3,10 @@ use alloy_rlp::Decodable;
 use alloy_rpc_types_debug::ExecutionWitness;
 use reth_trie::TrieAccount;
 
-use crate::mpt::{resolve_nodes, MptNode, MptNodeData, MptNodeReference};
+use crate::{
+    mpt::{resolve_nodes, MptNode, MptNodeData, MptNodeReference},
+    FromProofError,
+};
 
 // Builds tries from the witness state.
 //
@@ -12,7 +15,7 @@ use crate::mpt::{resolve_nodes, MptNode, MptNodeData, MptNodeReference};
 pub(crate) fn build_validated_tries(
     witness: &ExecutionWitness,
     pre_state_root: B256,
-) -> Result<(MptNode, HashMap<B256, MptNode>), String> {
+) -> Result<(MptNode, HashMap<B256, MptNode>), FromProofError> {
     // Step 1: Decode all RLP-encoded trie nodes and index by hash
     // IMPORTANT: Witness state contains both *state trie* nodes and *storage tries* nodes!
     let mut node_map: HashMap<MptNodeReference, MptNode> = HashMap::default();
@@ -20,8 +23,9 @@ pub(crate) fn build_validated_tries(
     let mut root_node: Option<MptNode> = None;
 
     for encoded in &witness.state {
-        let node = MptNode::decode(encoded).expect("Valid MPT node in witness");
         let hash = keccak256(encoded);
+        let node = MptNode::decode(encoded)
+            .map_err(|err| FromProofError::InvalidWitnessNode(hash, err))?;
         if hash == pre_state_root {
             root_node = Some(node.clone());
         }
@@ -32,15 +36,26 @@ pub(crate) fn build_validated_tries(
     // Step 2: Use root_node or fallback to Digest
     let root = root_node.unwrap_or_else(|| MptNodeData::Digest(pre_state_root).into());
 
-    // Build state trie.
-    let mut raw_storage_tries = vec![];
+    // Build state trie, and verify that it was built correctly - confirm tree hash with pre state
+    // root.
     let state_trie = resolve_nodes(&root, &node_map);
+    validate_state_trie(&state_trie, pre_state_root)?;
 
+    let mut raw_storage_tries = vec![];
+    let mut invalid_account = None;
     state_trie.for_each_leaves(|key, mut value| {
-        let account = TrieAccount::decode(&mut value).unwrap();
         let hashed_address = B256::from_slice(key);
-        raw_storage_tries.push((hashed_address, account.storage_root));
+        match TrieAccount::decode(&mut value) {
+            Ok(account) => raw_storage_tries.push((hashed_address, account.storage_root)),
+            Err(err) => {
+                invalid_account
+                    .get_or_insert(FromProofError::InvalidWitnessAccount(hashed_address, err));
+            }
+        }
     });
+    if let Some(err) = invalid_account {
+        return Err(err);
+    }
 
     // Step 3: Build storage tries per account efficiently
     let mut storage_tries: HashMap<B256, MptNode> = HashMap::default();
@@ -58,47 +73,47 @@ pub(crate) fn build_validated_tries(
         let storage_trie = resolve_nodes(&root_node, &node_map);
 
         if storage_trie.is_digest() {
-            panic!("Could not resolve storage trie for {storage_root}");
+            return Err(FromProofError::UnresolvedStorageTrie(hashed_address, storage_root));
         }
 
         // Insert resolved storage trie.
         storage_tries.insert(hashed_address, storage_trie);
     }
 
-    // Step 3a: Verify that state_trie was built correctly - confirm tree hash with pre state root.
-    validate_state_trie(&state_trie, pre_state_root);
-
-    // Step 3b: Verify that each storage trie matches the declared storage_root in the state trie.
+    // Step 4: Verify that each storage trie matches the declared storage_root in the state trie.
     validate_storage_tries(&state_trie, &storage_tries)?;
 
     Ok((state_trie, storage_tries))
 }
 
 // Validate that state_trie was built correctly - confirm tree hash with pre state root.
-fn validate_state_trie(state_trie: &MptNode, pre_state_root: B256) {
-    if state_trie.hash() != pre_state_root {
-        panic!("Computed state root does not match pre_state_root");
+fn validate_state_trie(state_trie: &MptNode, pre_state_root: B256) -> Result<(), FromProofError> {
+    let state_root = state_trie.hash();
+    if state_root != pre_state_root {
+        return Err(FromProofError::MismatchedStateRoot(state_root, pre_state_root));
     }
+
+    Ok(())
 }
 
 // Validates that each storage trie matches the declared storage_root in the state trie.
 fn validate_storage_tries(
     state_trie: &MptNode,
     storage_tries: &HashMap<B256, MptNode>,
-) -> Result<(), String> {
+) -> Result<(), FromProofError> {
     for (hashed_address, storage_trie) in storage_tries.iter() {
         let account = state_trie
-            .get_rlp::<TrieAccount>(hashed_address.as_slice())
-            .map_err(|_| "Failed to decode account from state trie")?
-            .ok_or("Account not found in state trie")?;
+            .get_rlp::<TrieAccount>(hashed_address.as_slice())?
+            .ok_or(FromProofError::MissingWitnessAccount(*hashed_address))?;
 
         let storage_root = account.storage_root;
         let actual_hash = storage_trie.hash();
 
         if storage_root != actual_hash {
-            return Err(format!(
-                "Mismatched storage root for address hash {:?}: expected {:?}, got {:?}",
-                hashed_address, storage_root, actual_hash
+            return Err(FromProofError::MismatchedWitnessStorageRoot(
+                *hashed_address,
+                actual_hash,
+                storage_root,
             ));
         }
     }
@@ -0,0 +1,327 @@
+//! A flat encoding of [`EthereumState`], used to serialize the zkVM input.
+//!
+//! Instead of serializing each trie as a tree of nodes, the RLP-encoded nodes of all the tries
+//! are deduplicated into a single list, along with the roots of the tries. The tries are rebuilt
+//! from the roots by looking the nodes up by hash, and each node is only decoded when first
+//! accessed.
+
+use std::{collections::BTreeMap, sync::Arc};
+
+use alloy_primitives::{keccak256, Bytes, B256};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::{
+    mpt::{resolve_nodes_by_hash, MptNode, MptNodeData, MptNodeReference, NodeStore, RlpBytes},
+    EthereumState, FromProofError,
+};
+
+/// The tries of an [`EthereumState`], as a deduplicated list of RLP-encoded nodes plus the
+/// roots of the tries.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FlatWitness {
+    /// The root of the state trie.
+    pub state_root: B256,
+    /// The roots of the storage tries, by hashed address.
+    pub storage_roots: Vec<(B256, B256)>,
+    /// The RLP-encoded nodes, sorted by hash.
+    pub nodes: Vec<Bytes>,
+}
+
+impl FlatWitness {
+    /// Rebuilds the tries of the state, whose nodes are decoded on first access.
+    ///
+    /// Fails if any of the nodes cannot be decoded.
+    pub fn into_state(self) -> Result<EthereumState, FromProofError> {
+        let store = Arc::new(NodeStore::new(self.nodes)?);
+        Ok(build_state(self.state_root, &self.storage_roots, &store))
+    }
+
+    /// Rebuilds the tries of the state and runs `replay` on them, then only keeps the nodes that
+    /// it decoded.
+    ///
+    /// The nodes left out are rebuilt as digests, so the roots of the tries are unchanged, and
+    /// replaying the same operations on the minimized state decodes the same nodes. As the nodes
+    /// decoded by the deletions depend on the order of the updates, `replay` should decode them
+    /// with [`EthereumState::decode_deleted_siblings`] if the state may be updated in another
+    /// order.
+    pub fn minimize<R>(
+        &mut self,
+        replay: impl FnOnce(EthereumState) -> R,
+    ) -> Result<R, FromProofError> {
+        let store = Arc::new(NodeStore::recording(self.nodes.iter().cloned())?);
+        let result = replay(build_state(self.state_root, &self.storage_roots, &store));
+
+        let decoded = store.decoded().expect("the store records the decoded nodes");
+        self.nodes.retain(|node| decoded.contains(&keccak256(node)));
+
+        Ok(result)
+    }
+
+    /// Returns the total length of the RLP-encoded nodes.
+    pub fn nodes_len(&self) -> usize {
+        self.nodes.iter().map(|node| node.len()).sum()
+    }
+}
+
+/// Rebuilds the tries with the given roots from the nodes of `store`.
+fn build_state(
+    state_root: B256,
+    storage_roots: &[(B256, B256)],
+    store: &Arc<NodeStore>,
+) -> EthereumState {
+    let state_trie = resolve_nodes_by_hash(state_root, store);
+    let storage_tries = storage_roots
+        .iter()
+        .map(|(hashed_address, storage_root)| {
+            (*hashed_address, resolve_nodes_by_hash(*storage_root, store))
+        })
+        .collect();
+
+    EthereumState { state_trie, storage_tries }
+}
+
+impl From<&EthereumState> for FlatWitness {
+    fn from(state: &EthereumState) -> Self {
+        let mut nodes = BTreeMap::new();
+
+        let state_root = collect_nodes(&state.state_trie, &mut nodes);
+        let mut storage_roots: Vec<_> = state
+            .storage_tries
+            .iter()
+            .map(|(hashed_address, storage_trie)| {
+                (*hashed_address, collect_nodes(storage_trie, &mut nodes))
+            })
+            .collect();
+        storage_roots.sort_unstable();
+
+        Self { state_root, storage_roots, nodes: nodes.into_values().collect() }
+    }
+}
+
+/// Collects the nodes of the trie referenced by hash, including the root, and returns the root
+/// hash.
+fn collect_nodes(trie: &MptNode, nodes: &mut BTreeMap<B256, Bytes>) -> B256 {
+    let root = trie.hash();
+
+    if !matches!(trie.as_data(), MptNodeData::Null | MptNodeData::Digest(_)) {
+        nodes.entry(root).or_insert_with(|| trie.to_rlp().into());
+        collect_children(trie, nodes);
+    }
+
+    root
+}
+
+fn collect_children(node: &MptNode, nodes: &mut BTreeMap<B256, Bytes>) {
+    let children: Vec<&MptNode> = match node.as_data() {
+        MptNodeData::Null | MptNodeData::Leaf(_, _) | MptNodeData::Digest(_) => return,
+        MptNodeData::Branch(children) => children.iter().flatten().map(|child| &**child).collect(),
+        MptNodeData::Extension(_, child) => vec![&**child],
+    };
+
+    for child in children {
+        if child.is_digest() {
+            continue;
+        }
+
+        // the nodes with a short encoding are inlined in their parent
+        if let MptNodeReference::Digest(digest) = child.reference() {
+            nodes.entry(digest).or_insert_with(|| child.to_rlp().into());
+        }
+
+        collect_children(child, nodes);
+    }
+}
+
+/// Serializes an [`EthereumState`] as a [`FlatWitness`], for use with `#[serde(with)]`.
+pub fn serialize<S: Serializer>(state: &EthereumState, serializer: S) -> Result<S::Ok, S::Error> {
+    FlatWitness::from(state).serialize(serializer)
+}
+
+/// Deserializes an [`EthereumState`] from a [`FlatWitness`], for use with `#[serde(with)]`.
+pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<EthereumState, D::Error> {
+    FlatWitness::deserialize(deserializer)?.into_state().map_err(serde::de::Error::custom)
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy_primitives::{map::HashMap, U256};
+    use reth_trie::HashedPostState;
+
+    use super::*;
+
+    #[test]
+    fn test_flat_witness_roundtrip() {
+        let mut storage_trie = MptNode::default();
+        for i in 0..64u64 {
+            storage_trie.insert_rlp(keccak256(i.to_be_bytes()).as_slice(), U256::from(i)).unwrap();
+        }
+
+        let mut state_trie = MptNode::default();
+        for i in 0..64u64 {
+            state_trie
+                .insert_rlp(keccak256(i.to_be_bytes()).as_slice(), storage_trie.hash())
+                .unwrap();
+        }
+
+        // share the same storage trie between two accounts, and add a tiny one
+        let mut tiny_trie = MptNode::default();
+        tiny_trie.insert_rlp(&[0x01], 1u8).unwrap();
+
+        let mut storage_tries = HashMap::default();
+        storage_tries.insert(keccak256([0u8]), storage_trie.clone());
+        storage_tries.insert(keccak256([1u8]), storage_trie.clone());
+        storage_tries.insert(keccak256([2u8]), tiny_trie);
+        storage_tries.insert(keccak256([3u8]), MptNode::default());
+
+        let state = EthereumState { state_trie, storage_tries };
+        let witness = FlatWitness::from(&state);
+
+        assert_eq!(witness.storage_roots.len(), 4);
+        assert_eq!(witness.nodes.len(), storage_trie.size() + state.state_trie.size() + 1);
+
+        let rebuilt = witness.into_state().unwrap();
+        assert_eq!(rebuilt, state);
+        assert_eq!(rebuilt.state_root(), state.state_root());
+    }
+
+    #[test]
+    fn test_flat_witness_keeps_digests() {
+        let mut trie = MptNode::default();
+        for i in 0..16u64 {
+            trie.insert_rlp(keccak256(i.to_be_bytes()).as_slice(), U256::from(i)).unwrap();
+        }
+
+        let state = EthereumState {
+            state_trie: MptNodeData::Digest(trie.hash()).into(),
+            storage_tries: HashMap::default(),
+        };
+
+        let rebuilt = FlatWitness::from(&state).into_state().unwrap();
+        assert_eq!(rebuilt, state);
+    }
+
+    #[test]
+    fn test_flat_witness_update() {
+        let mut trie = MptNode::default();
+        for i in 0..64u64 {
+            trie.insert_rlp(keccak256(i.to_be_bytes()).as_slice(), U256::from(i)).unwrap();
+        }
+
+        let mut state = EthereumState { state_trie: trie, storage_tries: HashMap::default() };
+        let mut rebuilt = FlatWitness::from(&state).into_state().unwrap();
+
+        // the modified nodes must be re-encoded, the untouched ones are kept as is
+        for i in 32..96u64 {
+            let key = keccak256(i.to_be_bytes());
+            state.state_trie.insert_rlp(key.as_slice(), U256::from(i + 1)).unwrap();
+            rebuilt.state_trie.insert_rlp(key.as_slice(), U256::from(i + 1)).unwrap();
+        }
+        for i in 0..16u64 {
+            let key = keccak256(i.to_be_bytes());
+            state.state_trie.delete(key.as_slice()).unwrap();
+            rebuilt.state_trie.delete(key.as_slice()).unwrap();
+        }
+
+        assert_eq!(rebuilt.state_root(), state.state_root());
+        assert_eq!(rebuilt, state);
+    }
+
+    #[test]
+    fn test_flat_witness_minimize() {
+        let mut trie = MptNode::default();
+        for i in 0..64u64 {
+            trie.insert_rlp(keccak256(i.to_be_bytes()).as_slice(), U256::from(i)).unwrap();
+        }
+
+        let state = EthereumState { state_trie: trie, storage_tries: HashMap::default() };
+        let mut witness = FlatWitness::from(&state);
+        let nodes_len = witness.nodes_len();
+
+        let replay = |mut state: EthereumState| {
+            let value = state.state_trie.get_rlp::<U256>(keccak256(0u64.to_be_bytes()).as_slice());
+            state
+                .state_trie
+                .insert_rlp(keccak256(64u64.to_be_bytes()).as_slice(), U256::from(64))
+                .unwrap();
+            state.state_trie.delete(keccak256(1u64.to_be_bytes()).as_slice()).unwrap();
+            (value.unwrap(), state.state_root())
+        };
+        let expected = replay(state.clone());
+        assert_eq!(witness.minimize(replay).unwrap(), expected);
+        assert!(witness.nodes_len() < nodes_len);
+
+        // the minimized state has the same root, and supports the same operations
+        let minimized = witness.into_state().unwrap();
+        assert_eq!(minimized.state_root(), state.state_root());
+        assert_eq!(replay(minimized.clone()), expected);
+
+        // but the other keys are not resolved anymore
+        assert!((2..64u64)
+            .any(|i| minimized.state_trie.get(keccak256(i.to_be_bytes()).as_slice()).is_err()));
+    }
+
+    #[test]
+    fn test_flat_witness_minimize_any_order() {
+        // keys under distinct children of the root branch
+        let key = |i: u8| B256::repeat_byte(i << 4 | i);
+        let mut trie = MptNode::default();
+        for i in 0..3u8 {
+            trie.insert_rlp(key(i).as_slice(), U256::from(i)).unwrap();
+        }
+
+        let state = EthereumState { state_trie: trie, storage_tries: HashMap::default() };
+        let mut witness = FlatWitness::from(&state);
+
+        let mut deletions = HashedPostState::default();
+        deletions.accounts.insert(key(0), None);
+        deletions.accounts.insert(key(1), None);
+
+        // inserts the key 3 and deletes the keys 0 and 1, in the given order
+        let apply = |state: &mut EthereumState, order: [u8; 3]| {
+            for i in order {
+                if i == 3 {
+                    state.state_trie.insert_rlp(key(i).as_slice(), U256::from(i)).unwrap();
+                } else {
+                    state.state_trie.delete(key(i).as_slice()).unwrap();
+                }
+            }
+            state.state_root()
+        };
+
+        // inserting first, the root branch never collapses, so its remaining child is not
+        // decoded by the updates themselves
+        let expected = witness
+            .minimize(|mut state| {
+                state.decode_deleted_siblings(&deletions);
+                apply(&mut state, [3, 0, 1])
+            })
+            .unwrap();
+
+        for order in [[3, 0, 1], [0, 1, 3], [0, 3, 1], [1, 0, 3]] {
+            let mut minimized = witness.clone().into_state().unwrap();
+            assert_eq!(apply(&mut minimized, order), expected);
+        }
+    }
+
+    #[test]
+    fn test_flat_witness_rejects_invalid_nodes() {
+        let mut trie = MptNode::default();
+        for i in 0..16u64 {
+            trie.insert_rlp(keccak256(i.to_be_bytes()).as_slice(), U256::from(i)).unwrap();
+        }
+        let state = EthereumState { state_trie: trie, storage_tries: HashMap::default() };
+        let mut witness = FlatWitness::from(&state);
+
+        // an undecodable node, referenced by hash as a storage root
+        let garbage = Bytes::from_static(&[0xc3, 0x01, 0x02, 0x03]);
+        let garbage_hash = keccak256(&garbage);
+        witness.storage_roots.push((keccak256([0u8]), garbage_hash));
+        witness.nodes.push(garbage);
+
+        let err = witness.minimize(|_| ()).unwrap_err();
+        assert!(matches!(err, FromProofError::InvalidWitnessNode(hash, _) if hash == garbage_hash));
+
+        let err = witness.into_state().unwrap_err();
+        assert!(matches!(err, FromProofError::InvalidWitnessNode(hash, _) if hash == garbage_hash));
+    }
+}
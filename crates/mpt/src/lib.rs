@@ -1,20 +1,23 @@
 #![cfg_attr(not(test), warn(unused_crate_dependencies))]
 
-use alloy_primitives::{keccak256, map::HashMap, Address, B256};
-use alloy_rpc_types::EIP1186AccountProofResponse;
+use alloy_primitives::{keccak256, map::HashMap, Address, B256, U256};
+use alloy_rpc_types::{EIP1186AccountProofResponse, EIP1186StorageProof};
 use reth_trie::{AccountProof, HashedPostState, HashedStorage, TrieAccount};
 use serde::{Deserialize, Serialize};
 
 #[cfg(feature = "execution-witness")]
 mod execution_witness;
 
+pub mod flat_witness;
+pub use flat_witness::FlatWitness;
+
 /// Module containing MPT code adapted from `zeth`.
 mod mpt;
-pub use mpt::Error;
 use mpt::{
     mpt_from_proof, parse_proof, proofs_to_tries, resolve_nodes, transition_proofs_to_tries,
-    MptNode,
+    EMPTY_ROOT, KECCAK_EMPTY,
 };
+pub use mpt::{Error, MptNode};
 
 /// Ethereum state trie and account storage tries.
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
@@ -72,56 +75,223 @@ impl EthereumState {
         Ok(state)
     }
 
+    /// Generates an EIP-1186 proof of the account and of the given storage slots.
+    ///
+    /// If the account or the slots do not exist, proofs of exclusion are generated.
+    pub fn account_proof(
+        &self,
+        address: Address,
+        storage_keys: &[B256],
+    ) -> Result<EIP1186AccountProofResponse, Error> {
+        let hashed_address = keccak256(address);
+        let account = self.state_trie.get_rlp::<TrieAccount>(hashed_address.as_slice())?;
+        let account_proof = self.state_trie.prove(hashed_address.as_slice())?;
+
+        let (nonce, balance, storage_hash, code_hash) = match account {
+            Some(account) => {
+                (account.nonce, account.balance, account.storage_root, account.code_hash)
+            }
+            None => (0, U256::ZERO, EMPTY_ROOT, KECCAK_EMPTY),
+        };
+
+        // the storage trie of a destroyed account may still be around
+        let empty_trie = MptNode::default();
+        let storage_trie = match self.storage_tries.get(&hashed_address) {
+            _ if storage_hash == EMPTY_ROOT => &empty_trie,
+            Some(storage_trie) => storage_trie,
+            None => return Err(Error::NodeNotResolved(storage_hash)),
+        };
+
+        let storage_proof = storage_keys
+            .iter()
+            .map(|key| {
+                let hashed_key = keccak256(key);
+                Ok(EIP1186StorageProof {
+                    key: (*key).into(),
+                    value: storage_trie.get_rlp(hashed_key.as_slice())?.unwrap_or_default(),
+                    proof: storage_trie.prove(hashed_key.as_slice())?,
+                })
+            })
+            .collect::<Result<_, Error>>()?;
+
+        Ok(EIP1186AccountProofResponse {
+            address,
+            balance,
+            code_hash,
+            nonce,
+            storage_hash,
+            account_proof,
+            storage_proof,
+        })
+    }
+
+    /// Builds Ethereum state tries from an execution witness, validated against the given state
+    /// root.
     #[cfg(feature = "execution-witness")]
     pub fn from_execution_witness(
         witness: &alloy_rpc_types_debug::ExecutionWitness,
         pre_state_root: B256,
-    ) -> Self {
+    ) -> Result<Self, FromProofError> {
         let (state_trie, storage_tries) =
-            execution_witness::build_validated_tries(witness, pre_state_root).unwrap();
+            execution_witness::build_validated_tries(witness, pre_state_root)?;
+
+        Ok(Self { state_trie, storage_tries })
+    }
 
-        Self { state_trie, storage_tries }
+    /// Returns the storage of the account with the given hashed address.
+    pub fn storage(&self, hashed_address: &B256) -> Result<AccountStorage<'_>, Error> {
+        if let Some(storage_trie) = self.storage_tries.get(hashed_address) {
+            return Ok(AccountStorage::Provided(storage_trie));
+        }
+
+        match self.state_trie.get_rlp::<TrieAccount>(hashed_address.as_slice())? {
+            Some(account) if account.storage_root != EMPTY_ROOT => {
+                Ok(AccountStorage::NotProvided(account.storage_root))
+            }
+            _ => Ok(AccountStorage::Empty),
+        }
     }
 
     /// Mutates state based on diffs provided in [`HashedPostState`].
-    pub fn update(&mut self, post_state: &HashedPostState) {
+    ///
+    /// Fails if the diffs touch accounts or slots whose nodes are not resolved, or modify the
+    /// storage of an account whose storage trie is not provided.
+    pub fn update(&mut self, post_state: &HashedPostState) -> Result<(), StateError> {
+        let mut storage_roots = self.prepare_storage_tries(post_state)?;
+
         for (hashed_address, account) in post_state.accounts.iter() {
-            match account {
-                Some(account) => {
-                    let state_storage = &post_state
-                        .storages
-                        .get(hashed_address)
-                        .cloned()
-                        .unwrap_or_else(|| HashedStorage::new(false));
-                    let storage_root = {
-                        let storage_trie = self.storage_tries.entry(*hashed_address).or_default();
-
-                        if state_storage.wiped {
-                            storage_trie.clear();
-                        }
-
-                        for (key, value) in state_storage.storage.iter() {
-                            let key = key.as_slice();
-                            if value.is_zero() {
-                                storage_trie.delete(key).unwrap();
-                            } else {
-                                storage_trie.insert_rlp(key, *value).unwrap();
-                            }
-                        }
-
-                        storage_trie.hash()
-                    };
+            if account.is_none() {
+                continue;
+            }
+
+            if let Some(storage_trie) = self.storage_tries.get_mut(hashed_address) {
+                let storage_root = update_storage_trie(
+                    hashed_address,
+                    storage_trie,
+                    post_state.storages.get(hashed_address),
+                )?;
+                storage_roots.insert(*hashed_address, storage_root);
+            }
+        }
+
+        self.update_accounts(post_state, &storage_roots)
+    }
 
+    /// Mutates state based on diffs provided in [`HashedPostState`], updating and hashing the
+    /// storage tries in parallel.
+    ///
+    /// The resulting state is identical to the one of [`EthereumState::update`].
+    #[cfg(feature = "parallel")]
+    pub fn par_update(&mut self, post_state: &HashedPostState) -> Result<(), StateError> {
+        use rayon::prelude::*;
+
+        let mut storage_roots = self.prepare_storage_tries(post_state)?;
+
+        let storage_tries: Vec<_> = self
+            .storage_tries
+            .iter_mut()
+            .filter(|(hashed_address, _)| {
+                matches!(post_state.accounts.get(*hashed_address), Some(Some(_)))
+            })
+            .collect();
+        let updated_storage_roots: Vec<_> = storage_tries
+            .into_par_iter()
+            .map(|(hashed_address, storage_trie)| {
+                update_storage_trie(
+                    hashed_address,
+                    storage_trie,
+                    post_state.storages.get(hashed_address),
+                )
+                .map(|storage_root| (*hashed_address, storage_root))
+            })
+            .collect::<Result<_, StateError>>()?;
+        storage_roots.extend(updated_storage_roots);
+
+        self.update_accounts(post_state, &storage_roots)
+    }
+
+    /// Provides empty storage tries to the updated accounts that have no storage, and returns the
+    /// storage roots of the updated accounts whose storage trie is not provided, but unchanged.
+    fn prepare_storage_tries(
+        &mut self,
+        post_state: &HashedPostState,
+    ) -> Result<HashMap<B256, B256>, StateError> {
+        let mut storage_roots = HashMap::default();
+
+        for (hashed_address, account) in post_state.accounts.iter() {
+            if account.is_none() || self.storage_tries.contains_key(hashed_address) {
+                continue;
+            }
+
+            let storage = post_state.storages.get(hashed_address);
+            let storage_root = match storage {
+                Some(storage) if storage.wiped => EMPTY_ROOT,
+                _ => match self.storage(hashed_address) {
+                    Ok(AccountStorage::NotProvided(storage_root)) => storage_root,
+                    Ok(_) => EMPTY_ROOT,
+                    Err(err) => return Err(StateError::UnresolvedAccount(*hashed_address, err)),
+                },
+            };
+
+            if storage_root == EMPTY_ROOT {
+                self.storage_tries.insert(*hashed_address, MptNode::default());
+            } else if storage.is_none_or(|storage| storage.storage.is_empty()) {
+                storage_roots.insert(*hashed_address, storage_root);
+            } else {
+                return Err(StateError::MissingStorageTrie(*hashed_address));
+            }
+        }
+
+        Ok(storage_roots)
+    }
+
+    /// Applies the account diffs to the state trie, given the updated storage roots.
+    fn update_accounts(
+        &mut self,
+        post_state: &HashedPostState,
+        storage_roots: &HashMap<B256, B256>,
+    ) -> Result<(), StateError> {
+        for (hashed_address, account) in post_state.accounts.iter() {
+            let result = match account {
+                Some(account) => {
                     let state_account = TrieAccount {
                         nonce: account.nonce,
                         balance: account.balance,
-                        storage_root,
+                        storage_root: storage_roots[hashed_address],
                         code_hash: account.get_bytecode_hash(),
                     };
-                    self.state_trie.insert_rlp(hashed_address.as_slice(), state_account).unwrap();
+                    self.state_trie.insert_rlp(hashed_address.as_slice(), state_account)
                 }
-                None => {
-                    self.state_trie.delete(hashed_address.as_slice()).unwrap();
+                None => self.state_trie.delete(hashed_address.as_slice()),
+            };
+            result.map_err(|err| StateError::UnresolvedAccount(*hashed_address, err))?;
+        }
+
+        Ok(())
+    }
+
+    /// Decodes the nodes that the deletions of the post state may need, whatever the order in
+    /// which [`EthereumState::update`] applies them, see [`MptNode::decode_siblings`].
+    pub fn decode_deleted_siblings(&self, post_state: &HashedPostState) {
+        for (hashed_address, account) in post_state.accounts.iter() {
+            if account.is_none() {
+                self.state_trie.decode_siblings(hashed_address.as_slice());
+                continue;
+            }
+
+            let (Some(storage_trie), Some(storage)) =
+                (self.storage_tries.get(hashed_address), post_state.storages.get(hashed_address))
+            else {
+                continue;
+            };
+            // a wiped storage trie is cleared before being updated
+            if storage.wiped {
+                continue;
+            }
+
+            for (key, value) in storage.storage.iter() {
+                if value.is_zero() {
+                    storage_trie.decode_siblings(key.as_slice());
                 }
             }
         }
@@ -133,6 +303,53 @@ impl EthereumState {
     }
 }
 
+/// Applies the storage diffs to the storage trie, and returns its new root.
+fn update_storage_trie(
+    hashed_address: &B256,
+    storage_trie: &mut MptNode,
+    storage: Option<&HashedStorage>,
+) -> Result<B256, StateError> {
+    if let Some(storage) = storage {
+        if storage.wiped {
+            storage_trie.clear();
+        }
+
+        for (key, value) in storage.storage.iter() {
+            let result = if value.is_zero() {
+                storage_trie.delete(key.as_slice())
+            } else {
+                storage_trie.insert_rlp(key.as_slice(), *value)
+            };
+            result.map_err(|err| StateError::UnresolvedStorage(*hashed_address, *key, err))?;
+        }
+    }
+
+    Ok(storage_trie.hash())
+}
+
+/// The storage of an account in an [`EthereumState`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AccountStorage<'a> {
+    /// The account does not exist or has no storage, so its storage trie is not needed.
+    Empty,
+    /// The storage trie is provided, although the nodes that were not needed may be left as
+    /// digests.
+    Provided(&'a MptNode),
+    /// The account has storage, with the given root, but its storage trie is not provided.
+    NotProvided(B256),
+}
+
+/// Errors that occur when updating an [`EthereumState`] that lacks the needed nodes.
+#[derive(Debug, thiserror::Error)]
+pub enum StateError {
+    #[error("Account with address hash {} cannot be updated: {}", .0, .1)]
+    UnresolvedAccount(B256, Error),
+    #[error("Storage of address hash {} is modified but its trie is not provided", .0)]
+    MissingStorageTrie(B256),
+    #[error("Slot {} of address hash {} cannot be updated: {}", .1, .0, .2)]
+    UnresolvedStorage(B256, B256, Error),
+}
+
 #[derive(Debug, thiserror::Error)]
 pub enum FromProofError {
     #[error("Node {} is not found by hash", .0)]
@@ -148,4 +365,353 @@ pub enum FromProofError {
     // todo: Should decode return a decoder error?
     #[error("Error decoding proofs from bytes, {}", .0)]
     DecodingError(#[from] Error),
+    #[error("Witness node {} cannot be decoded: {}", .0, .1)]
+    InvalidWitnessNode(B256, Error),
+    #[error("Witness account with address hash {} cannot be decoded: {}", .0, .1)]
+    InvalidWitnessAccount(B256, alloy_rlp::Error),
+    #[error("Account with address hash {} is not found in the witness state trie", .0)]
+    MissingWitnessAccount(B256),
+    #[error("Witness storage trie of address hash {} cannot be resolved from root {}", .0, .1)]
+    UnresolvedStorageTrie(B256, B256),
+    #[error("Found mismatched witness storage root \n address hash {}, found {}, expected {}", .0, .1, .2)]
+    MismatchedWitnessStorageRoot(B256, B256, B256),
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use proptest::{collection::btree_map, option, prelude::*};
+    use reth_primitives_traits::Account;
+    use reth_trie::StorageProof;
+
+    use super::*;
+
+    #[test]
+    fn test_account_proof() {
+        let mut storage_trie = MptNode::default();
+        for i in 1..=64u64 {
+            let key = keccak256(B256::from(U256::from(i)));
+            storage_trie.insert_rlp(key.as_slice(), U256::from(i)).unwrap();
+        }
+
+        let address = Address::repeat_byte(0x01);
+        let mut state_trie = MptNode::default();
+        for i in 0..64u8 {
+            let storage_root = if i == 0x01 { storage_trie.hash() } else { EMPTY_ROOT };
+            let account = TrieAccount {
+                nonce: i as u64,
+                balance: U256::from(i),
+                storage_root,
+                code_hash: KECCAK_EMPTY,
+            };
+            state_trie.insert_rlp(keccak256(Address::repeat_byte(i)).as_slice(), account).unwrap();
+        }
+
+        let mut storage_tries = HashMap::default();
+        storage_tries.insert(keccak256(address), storage_trie.clone());
+        let state = EthereumState { state_trie, storage_tries };
+
+        // an existing and a missing slot
+        let storage_keys = [B256::from(U256::from(1)), B256::from(U256::from(100))];
+        let proof = state.account_proof(address, &storage_keys).unwrap();
+        assert_eq!(proof.nonce, 1);
+        assert_eq!(proof.storage_hash, storage_trie.hash());
+        assert_eq!(proof.storage_proof[0].value, U256::from(1));
+        assert_eq!(proof.storage_proof[1].value, U256::ZERO);
+
+        let rebuilt = EthereumState::from_account_proof(proof).unwrap();
+        assert_eq!(rebuilt.state_root(), state.state_root());
+        assert_eq!(rebuilt.storage_tries[&keccak256(address)].hash(), storage_trie.hash());
+
+        // a missing account
+        let proof = state.account_proof(Address::repeat_byte(0xff), &storage_keys).unwrap();
+        assert_eq!(proof.storage_hash, EMPTY_ROOT);
+        assert!(proof.storage_proof.iter().all(|storage_proof| storage_proof.proof.is_empty()));
+
+        let rebuilt = EthereumState::from_account_proof(proof).unwrap();
+        assert_eq!(rebuilt.state_root(), state.state_root());
+    }
+
+    #[cfg(feature = "execution-witness")]
+    #[test]
+    fn test_from_invalid_execution_witness() {
+        use alloy_primitives::Bytes;
+        use alloy_rpc_types_debug::ExecutionWitness;
+
+        let mut trie = MptNode::default();
+        for i in 0..16u64 {
+            let account = TrieAccount {
+                nonce: i,
+                balance: U256::ZERO,
+                storage_root: EMPTY_ROOT,
+                code_hash: KECCAK_EMPTY,
+            };
+            trie.insert_rlp(keccak256(i.to_be_bytes()).as_slice(), account).unwrap();
+        }
+
+        // an undecodable node
+        let garbage = Bytes::from_static(&[0xc3, 0x01, 0x02, 0x03]);
+        let garbage_hash = keccak256(&garbage);
+        let witness = ExecutionWitness { state: vec![garbage], ..Default::default() };
+        let err = EthereumState::from_execution_witness(&witness, trie.hash()).unwrap_err();
+        assert!(matches!(err, FromProofError::InvalidWitnessNode(hash, _) if hash == garbage_hash));
+
+        // an account leaf that is not an account
+        let mut trie = MptNode::default();
+        trie.insert(keccak256([0u8]).as_slice(), vec![0x2a]).unwrap();
+        let witness =
+            ExecutionWitness { state: vec![alloy_rlp::encode(&trie).into()], ..Default::default() };
+        let err = EthereumState::from_execution_witness(&witness, trie.hash()).unwrap_err();
+        assert!(matches!(err, FromProofError::InvalidWitnessAccount(_, _)));
+    }
+
+    #[test]
+    fn test_update_storage_availability() {
+        let mut storage_trie = MptNode::default();
+        for i in 1..=16u64 {
+            storage_trie
+                .insert_rlp(keccak256(B256::from(U256::from(i))).as_slice(), U256::from(i))
+                .unwrap();
+        }
+
+        let with_storage = keccak256(address(1));
+        let without_storage = keccak256(address(2));
+        let mut state_trie = MptNode::default();
+        for (hashed_address, storage_root) in
+            [(with_storage, storage_trie.hash()), (without_storage, EMPTY_ROOT)]
+        {
+            let account = TrieAccount {
+                nonce: 1,
+                balance: U256::ZERO,
+                storage_root,
+                code_hash: KECCAK_EMPTY,
+            };
+            state_trie.insert_rlp(hashed_address.as_slice(), account).unwrap();
+        }
+
+        let mut full_state = EthereumState {
+            state_trie: state_trie.clone(),
+            storage_tries: HashMap::from_iter([(with_storage, storage_trie.clone())]),
+        };
+        let state = EthereumState { state_trie, storage_tries: HashMap::default() };
+        assert_eq!(
+            state.storage(&with_storage).unwrap(),
+            AccountStorage::NotProvided(storage_trie.hash())
+        );
+        assert_eq!(state.storage(&without_storage).unwrap(), AccountStorage::Empty);
+        assert_eq!(state.storage(&keccak256(address(3))).unwrap(), AccountStorage::Empty);
+
+        // the storage does not need to be provided to update the account only
+        let account = Account { nonce: 2, balance: U256::ZERO, bytecode_hash: None };
+        let mut post_state = HashedPostState::default();
+        post_state.accounts.insert(with_storage, Some(account));
+        post_state.accounts.insert(without_storage, Some(account));
+        post_state.storages.insert(
+            without_storage,
+            HashedStorage::from_iter(false, [(keccak256(slot(1)), U256::from(1))]),
+        );
+
+        let mut updated = state.clone();
+        updated.update(&post_state).unwrap();
+        full_state.update(&post_state).unwrap();
+        assert_eq!(updated.state_root(), full_state.state_root());
+
+        // but it does to update the slots
+        post_state.storages.insert(
+            with_storage,
+            HashedStorage::from_iter(false, [(keccak256(slot(1)), U256::from(2))]),
+        );
+        let err = state.clone().update(&post_state).unwrap_err();
+        assert!(matches!(
+            err,
+            StateError::MissingStorageTrie(hashed_address) if hashed_address == with_storage
+        ));
+
+        // and the modified slots must be resolved
+        let mut partial_state = state.clone();
+        partial_state
+            .storage_tries
+            .insert(with_storage, mpt::MptNodeData::Digest(storage_trie.hash()).into());
+        let err = partial_state.update(&post_state).unwrap_err();
+        assert!(matches!(
+            err,
+            StateError::UnresolvedStorage(hashed_address, _, Error::NodeNotResolved(_))
+                if hashed_address == with_storage
+        ));
+    }
+
+    #[cfg(feature = "parallel")]
+    fn assert_par_update_eq(state: &mut EthereumState, post_state: &HashedPostState) {
+        let mut expected = state.clone();
+        expected.update(post_state).unwrap();
+        state.par_update(post_state).unwrap();
+
+        assert_eq!(*state, expected);
+        assert_eq!(state.state_root(), expected.state_root());
+    }
+
+    #[cfg(feature = "parallel")]
+    #[test]
+    fn test_par_update() {
+        let mut state = EthereumState {
+            state_trie: MptNode::default(),
+            storage_tries: HashMap::with_hasher(Default::default()),
+        };
+
+        let mut post_state = HashedPostState::default();
+        for i in 0..32u64 {
+            let hashed_address = keccak256(i.to_be_bytes());
+            let account = Account { nonce: i, balance: U256::from(i), bytecode_hash: None };
+            post_state.accounts.insert(hashed_address, Some(account));
+            post_state.storages.insert(
+                hashed_address,
+                HashedStorage::from_iter(
+                    false,
+                    (1..=i).map(|j| (keccak256(j.to_be_bytes()), U256::from(j))),
+                ),
+            );
+        }
+        assert_par_update_eq(&mut state, &post_state);
+
+        // destroy, wipe and clear slots of some of the accounts
+        let mut post_state = HashedPostState::default();
+        for i in 0..32u64 {
+            let hashed_address = keccak256(i.to_be_bytes());
+            let account = Account { nonce: i + 1, balance: U256::from(i), bytecode_hash: None };
+            match i % 4 {
+                0 => {
+                    post_state.accounts.insert(hashed_address, None);
+                }
+                1 => {
+                    post_state.accounts.insert(hashed_address, Some(account));
+                    post_state.storages.insert(hashed_address, HashedStorage::new(true));
+                }
+                2 => {
+                    post_state.accounts.insert(hashed_address, Some(account));
+                    post_state.storages.insert(
+                        hashed_address,
+                        HashedStorage::from_iter(
+                            false,
+                            (1..=i / 2).map(|j| (keccak256(j.to_be_bytes()), U256::ZERO)),
+                        ),
+                    );
+                }
+                _ => {
+                    post_state.accounts.insert(hashed_address, Some(account));
+                }
+            }
+        }
+        assert_par_update_eq(&mut state, &post_state);
+    }
+
+    type Storage = BTreeMap<u8, u64>;
+
+    fn address(i: u8) -> Address {
+        Address::repeat_byte(i)
+    }
+
+    fn slot(i: u8) -> B256 {
+        B256::from(U256::from(i))
+    }
+
+    /// Creates the post state of the given changes, where `None` destroys the account and the
+    /// zero values delete the slots.
+    fn hashed_post_state(changes: &BTreeMap<u8, Option<(u64, Storage)>>) -> HashedPostState {
+        let mut post_state = HashedPostState::default();
+        for (i, change) in changes {
+            let hashed_address = keccak256(address(*i));
+            let Some((nonce, storage)) = change else {
+                post_state.accounts.insert(hashed_address, None);
+                continue;
+            };
+
+            let account = Account { nonce: *nonce, balance: U256::ZERO, bytecode_hash: None };
+            post_state.accounts.insert(hashed_address, Some(account));
+            post_state.storages.insert(
+                hashed_address,
+                HashedStorage::from_iter(
+                    false,
+                    storage.iter().map(|(j, value)| (keccak256(slot(*j)), U256::from(*value))),
+                ),
+            );
+        }
+
+        post_state
+    }
+
+    /// Converts a proof as done by the RPC database.
+    fn to_account_proof(proof: EIP1186AccountProofResponse) -> AccountProof {
+        let storage_proofs = proof
+            .storage_proof
+            .into_iter()
+            .map(|storage_proof| {
+                let mut sp = StorageProof::new(storage_proof.key.as_b256());
+                sp.value = storage_proof.value;
+                sp.proof = storage_proof.proof;
+                sp
+            })
+            .collect();
+
+        AccountProof {
+            address: proof.address,
+            info: None,
+            proof: proof.account_proof,
+            storage_root: proof.storage_hash,
+            storage_proofs,
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn test_transition_proofs_to_tries(
+            accounts in btree_map(
+                0..32u8,
+                (1..100u64, btree_map(0..16u8, 1..100u64, 0..8)),
+                1..16,
+            ),
+            changes in btree_map(
+                0..32u8,
+                option::of((1..100u64, btree_map(0..16u8, 0..100u64, 0..8))),
+                0..16,
+            ),
+        ) {
+            let mut pre_state = EthereumState {
+                state_trie: MptNode::default(),
+                storage_tries: HashMap::default(),
+            };
+            pre_state.update(&hashed_post_state(
+                &accounts.into_iter().map(|(i, account)| (i, Some(account))).collect(),
+            )).unwrap();
+
+            let post_state = hashed_post_state(&changes);
+            let mut expected = pre_state.clone();
+            expected.update(&post_state).unwrap();
+
+            // fetch the proofs of the modified slots before and after the transition
+            let mut parent_proofs = HashMap::default();
+            let mut proofs = HashMap::default();
+            for (i, change) in &changes {
+                let storage_keys: Vec<_> = change
+                    .iter()
+                    .flat_map(|(_, storage)| storage.keys().map(|j| slot(*j)))
+                    .collect();
+                let parent_proof = pre_state.account_proof(address(*i), &storage_keys).unwrap();
+                parent_proofs.insert(address(*i), to_account_proof(parent_proof));
+                let proof = expected.account_proof(address(*i), &storage_keys).unwrap();
+                proofs.insert(address(*i), to_account_proof(proof));
+            }
+
+            let mut state = EthereumState::from_transition_proofs(
+                pre_state.state_root(),
+                &parent_proofs,
+                &proofs,
+            )
+            .unwrap();
+            prop_assert_eq!(state.state_root(), pre_state.state_root());
+
+            state.update(&post_state).unwrap();
+            prop_assert_eq!(state.state_root(), expected.state_root());
+        }
+    }
 }
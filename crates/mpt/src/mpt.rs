@@ -20,7 +20,11 @@
 #![allow(dead_code)]
 
 use alloc::boxed::Box;
-use alloy_primitives::{b256, map::HashMap, B256};
+use alloy_primitives::{
+    b256, keccak256,
+    map::{HashMap, HashSet},
+    Bytes, B256,
+};
 use alloy_rlp::Encodable;
 use core::{
     cmp,
@@ -28,10 +32,10 @@ use core::{
     iter, mem,
 };
 use reth_trie::{AccountProof, Nibbles};
-use std::sync::Mutex;
+use std::sync::{Arc, Mutex, OnceLock};
 
 use rlp::{Decodable, DecoderError, Prototype, Rlp};
-use serde::{Deserialize, Serialize};
+use serde::{ser::SerializeStruct, Deserialize, Deserializer, Serialize, Serializer};
 use thiserror::Error as ThisError;
 
 use alloy_primitives::Address;
@@ -91,19 +95,26 @@ pub fn keccak(data: impl AsRef<[u8]>) -> [u8; 32] {
 /// optimizing storage. However, operations targeting a truncated part will fail and
 /// return an error. Another distinction of this implementation is that branches cannot
 /// store values, aligning with the construction of MPTs in Ethereum.
-#[derive(Debug, Default, Serialize, Deserialize)]
+#[derive(Debug)]
 pub struct MptNode {
-    /// The type and data of the node.
-    data: MptNodeData,
+    /// The type and data of the node, decoded from `encoded` on first access.
+    data: OnceLock<MptNodeData>,
+    /// The RLP encoding of a lazily decoded node. It is dropped when the node is modified.
+    encoded: Option<EncodedNode>,
     /// Cache for a previously computed reference of this node. This is skipped during
     /// serialization.
-    #[serde(skip)]
     cached_reference: Mutex<Option<MptNodeReference>>,
 }
 
+impl Default for MptNode {
+    fn default() -> Self {
+        MptNodeData::Null.into()
+    }
+}
+
 impl Ord for MptNode {
     fn cmp(&self, other: &Self) -> cmp::Ordering {
-        self.data.cmp(&other.data)
+        self.as_data().cmp(other.as_data())
     }
 }
 
@@ -117,7 +128,7 @@ impl Eq for MptNode {}
 
 impl PartialEq for MptNode {
     fn eq(&self, other: &Self) -> bool {
-        self.data == other.data
+        self.as_data() == other.as_data()
     }
 }
 
@@ -125,11 +136,62 @@ impl Clone for MptNode {
     fn clone(&self) -> Self {
         Self {
             data: self.data.clone(),
+            encoded: self.encoded.clone(),
             cached_reference: Mutex::new(self.cached_reference.lock().unwrap().clone()),
         }
     }
 }
 
+impl Serialize for MptNode {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut node = serializer.serialize_struct("MptNode", 1)?;
+        node.serialize_field("data", self.as_data())?;
+        node.end()
+    }
+}
+
+impl<'de> Deserialize<'de> for MptNode {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(rename = "MptNode")]
+        struct Node {
+            data: MptNodeData,
+        }
+
+        Ok(Node::deserialize(deserializer)?.data.into())
+    }
+}
+
+/// The RLP encoding of a node, along with the store of the nodes it may reference by hash.
+///
+/// The node is only decoded when its data is first accessed, and its children referenced by hash
+/// are then lazily decoded in turn.
+#[derive(Clone)]
+struct EncodedNode {
+    digest: B256,
+    rlp: Bytes,
+    store: Arc<NodeStore>,
+}
+
+impl EncodedNode {
+    fn decode(&self) -> MptNodeData {
+        if let Some(decoded) = &self.store.decoded {
+            decoded.lock().unwrap().insert(self.digest);
+        }
+
+        // the nodes are validated when the store is built
+        let mut data = MptNode::decode(&self.rlp).expect("invalid trie node").into_data();
+        resolve_lazily(&mut data, &self.store);
+        data
+    }
+}
+
+impl Debug for EncodedNode {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_tuple("EncodedNode").field(&self.rlp).finish()
+    }
+}
+
 /// Represents custom error types for the sparse Merkle Patricia Trie (MPT).
 ///
 /// These errors cover various scenarios that can occur during trie operations, such as
@@ -200,7 +262,7 @@ pub enum MptNodeReference {
 /// `cached_reference` field to `None`.
 impl From<MptNodeData> for MptNode {
     fn from(value: MptNodeData) -> Self {
-        Self { data: value, cached_reference: Mutex::new(None) }
+        Self { data: value.into(), encoded: None, cached_reference: Mutex::new(None) }
     }
 }
 
@@ -215,7 +277,13 @@ impl Encodable for MptNode {
     /// method handles different node data types and encodes them accordingly.
     #[inline]
     fn encode(&self, out: &mut dyn alloy_rlp::BufMut) {
-        match &self.data {
+        // a lazily decoded node is left as is
+        if let Some(encoded) = &self.encoded {
+            out.put_slice(&encoded.rlp);
+            return;
+        }
+
+        match self.as_data() {
             MptNodeData::Null => {
                 out.put_u8(alloy_rlp::EMPTY_STRING_CODE);
             }
@@ -250,6 +318,10 @@ impl Encodable for MptNode {
     /// determining the size requirements for storage or transmission.
     #[inline]
     fn length(&self) -> usize {
+        if let Some(encoded) = &self.encoded {
+            return encoded.rlp.len();
+        }
+
         let payload_length = self.payload_length();
         payload_length + alloy_rlp::length_of_length(payload_length)
     }
@@ -275,7 +347,9 @@ impl Decodable for MptNode {
             Prototype::Null | Prototype::Data(0) => Ok(MptNodeData::Null.into()),
             Prototype::List(2) => {
                 let path: Vec<u8> = rlp.val_at(0)?;
-                let prefix = path[0];
+                let Some(&prefix) = path.first() else {
+                    return Err(DecoderError::Custom("node with empty path"));
+                };
                 if (prefix & (2 << 4)) == 0 {
                     let node: MptNode = Decodable::decode(&rlp.at(1)?)?;
                     Ok(MptNodeData::Extension(path, Box::new(node)).into())
@@ -327,7 +401,8 @@ impl MptNode {
     /// This method effectively removes all key-value pairs from the trie.
     #[inline]
     pub fn clear(&mut self) {
-        self.data = MptNodeData::Null;
+        self.data = MptNodeData::Null.into();
+        self.encoded = None;
         self.invalidate_ref_cache();
     }
 
@@ -345,7 +420,20 @@ impl MptNode {
     /// manipulation.
     #[inline]
     pub fn as_data(&self) -> &MptNodeData {
-        &self.data
+        self.data.get_or_init(|| self.encoded.as_ref().expect("node without data").decode())
+    }
+
+    /// Retrieves the underlying data of the node for modification, dropping its encoding.
+    fn data_mut(&mut self) -> &mut MptNodeData {
+        self.as_data();
+        self.encoded = None;
+        self.data.get_mut().unwrap()
+    }
+
+    /// Consumes the node and returns its data.
+    fn into_data(self) -> MptNodeData {
+        self.as_data();
+        self.data.into_inner().unwrap()
     }
 
     /// Retrieves the [MptNodeReference] reference of the node when it's referenced inside
@@ -392,7 +480,7 @@ impl MptNode {
     /// This method provides a unique identifier for the node based on its content.
     #[inline]
     pub fn hash(&self) -> B256 {
-        match self.data {
+        match self.as_data() {
             MptNodeData::Null => EMPTY_ROOT,
             _ => match self.reference() {
                 MptNodeReference::Digest(digest) => digest,
@@ -423,7 +511,7 @@ impl MptNode {
     }
 
     fn calc_reference(&self) -> MptNodeReference {
-        match &self.data {
+        match self.as_data() {
             MptNodeData::Null => MptNodeReference::Bytes(vec![alloy_rlp::EMPTY_STRING_CODE]),
             MptNodeData::Digest(digest) => MptNodeReference::Digest(*digest),
             _ => {
@@ -443,7 +531,7 @@ impl MptNode {
     /// any key-value pairs.
     #[inline]
     pub fn is_empty(&self) -> bool {
-        matches!(&self.data, MptNodeData::Null)
+        matches!(self.as_data(), MptNodeData::Null)
     }
 
     /// Determines if the node represents a digest.
@@ -451,7 +539,7 @@ impl MptNode {
     /// A digest is a compact representation of a sub-trie, represented by its hash.
     #[inline]
     pub fn is_digest(&self) -> bool {
-        matches!(&self.data, MptNodeData::Digest(_))
+        matches!(self.as_data(), MptNodeData::Digest(_))
     }
 
     /// Retrieves the nibbles corresponding to the node's prefix.
@@ -460,7 +548,7 @@ impl MptNode {
     /// keys.
     #[inline]
     pub fn nibs(&self) -> Vec<u8> {
-        match &self.data {
+        match self.as_data() {
             MptNodeData::Null | MptNodeData::Branch(_) | MptNodeData::Digest(_) => vec![],
             MptNodeData::Leaf(prefix, _) | MptNodeData::Extension(prefix, _) => prefix_nibs(prefix),
         }
@@ -488,8 +576,46 @@ impl MptNode {
         }
     }
 
+    /// Generates an EIP-1186 proof for the given key.
+    ///
+    /// The proof contains the RLP-encoded nodes on the path to the key, starting with the root.
+    /// If the key is not present in the trie, it is a proof of exclusion. Nodes short enough to
+    /// be inlined in their parent are not part of the proof.
+    pub fn prove(&self, key: &[u8]) -> Result<Vec<Bytes>, Error> {
+        let mut proof = Vec::new();
+        if !self.is_empty() {
+            proof.push(self.to_rlp().into());
+        }
+        self.prove_internal(&to_nibs(key), &mut proof)?;
+
+        Ok(proof)
+    }
+
+    fn prove_internal(&self, key_nibs: &[u8], proof: &mut Vec<Bytes>) -> Result<(), Error> {
+        let next = match self.as_data() {
+            MptNodeData::Null | MptNodeData::Leaf(_, _) => None,
+            MptNodeData::Branch(nodes) => key_nibs
+                .split_first()
+                .and_then(|(i, tail)| nodes[*i as usize].as_ref().map(|node| (node, tail))),
+            MptNodeData::Extension(prefix, node) => {
+                key_nibs.strip_prefix(prefix_nibs(prefix).as_slice()).map(|tail| (node, tail))
+            }
+            MptNodeData::Digest(digest) => return Err(Error::NodeNotResolved(*digest)),
+        };
+
+        match next {
+            Some((node, tail)) => {
+                if let MptNodeReference::Digest(_) = node.reference() {
+                    proof.push(node.to_rlp().into());
+                }
+                node.prove_internal(tail, proof)
+            }
+            None => Ok(()),
+        }
+    }
+
     fn get_internal(&self, key_nibs: &[u8]) -> Result<Option<&[u8]>, Error> {
-        match &self.data {
+        match self.as_data() {
             MptNodeData::Null => Ok(None),
             MptNodeData::Branch(nodes) => {
                 if let Some((i, tail)) = key_nibs.split_first() {
@@ -528,8 +654,38 @@ impl MptNode {
         self.delete_internal(&to_nibs(key))
     }
 
+    /// Decodes the children of every branch on the path to the key.
+    ///
+    /// Deleting the key decodes the remaining child of a branch when it collapses, and which
+    /// branches collapse depends on the order of the other updates. Decoding all the children
+    /// makes the nodes needed by the deletion independent of that order.
+    pub fn decode_siblings(&self, key: &[u8]) {
+        self.decode_siblings_internal(&to_nibs(key))
+    }
+
+    fn decode_siblings_internal(&self, key_nibs: &[u8]) {
+        match self.as_data() {
+            MptNodeData::Null | MptNodeData::Leaf(_, _) | MptNodeData::Digest(_) => {}
+            MptNodeData::Branch(children) => {
+                for child in children.iter().flatten() {
+                    child.as_data();
+                }
+                if let Some((i, tail)) = key_nibs.split_first() {
+                    if let Some(child) = &children[*i as usize] {
+                        child.decode_siblings_internal(tail);
+                    }
+                }
+            }
+            MptNodeData::Extension(prefix, child) => {
+                if let Some(tail) = key_nibs.strip_prefix(prefix_nibs(prefix).as_slice()) {
+                    child.decode_siblings_internal(tail);
+                }
+            }
+        }
+    }
+
     fn delete_internal(&mut self, key_nibs: &[u8]) -> Result<bool, Error> {
-        match &mut self.data {
+        match self.data_mut() {
             MptNodeData::Null => return Ok(false),
             MptNodeData::Branch(children) => {
                 if let Some((i, tail)) = key_nibs.split_first() {
@@ -556,12 +712,12 @@ impl MptNode {
                 // if there is only exactly one node left, we need to convert the branch
                 if remaining.next().is_none() {
                     let mut orphan = node.take().unwrap();
-                    match &mut orphan.data {
+                    match orphan.data_mut() {
                         // if the orphan is a leaf, prepend the corresponding nib to it
                         MptNodeData::Leaf(prefix, orphan_value) => {
                             let new_nibs: Vec<_> =
                                 iter::once(index as u8).chain(prefix_nibs(prefix)).collect();
-                            self.data = MptNodeData::Leaf(
+                            *self.data_mut() = MptNodeData::Leaf(
                                 to_encoded_path(&new_nibs, true),
                                 mem::take(orphan_value),
                             );
@@ -570,14 +726,14 @@ impl MptNode {
                         MptNodeData::Extension(prefix, orphan_child) => {
                             let new_nibs: Vec<_> =
                                 iter::once(index as u8).chain(prefix_nibs(prefix)).collect();
-                            self.data = MptNodeData::Extension(
+                            *self.data_mut() = MptNodeData::Extension(
                                 to_encoded_path(&new_nibs, false),
                                 mem::take(orphan_child),
                             );
                         }
                         // if the orphan is a branch or digest, convert to an extension
                         MptNodeData::Branch(_) | MptNodeData::Digest(_) => {
-                            self.data = MptNodeData::Extension(
+                            *self.data_mut() = MptNodeData::Extension(
                                 to_encoded_path(&[index as u8], false),
                                 orphan,
                             );
@@ -590,7 +746,7 @@ impl MptNode {
                 if prefix_nibs(prefix) != key_nibs {
                     return Ok(false);
                 }
-                self.data = MptNodeData::Null;
+                *self.data_mut() = MptNodeData::Null;
             }
             MptNodeData::Extension(prefix, child) => {
                 let mut self_nibs = prefix_nibs(prefix);
@@ -604,21 +760,21 @@ impl MptNode {
 
                 // an extension can only point to a branch or a digest; since it's sub trie was
                 // modified, we need to make sure that this property still holds
-                match &mut child.data {
+                match child.data_mut() {
                     // if the child is empty, remove the extension
                     MptNodeData::Null => {
-                        self.data = MptNodeData::Null;
+                        *self.data_mut() = MptNodeData::Null;
                     }
                     // for a leaf, replace the extension with the extended leaf
                     MptNodeData::Leaf(prefix, value) => {
                         self_nibs.extend(prefix_nibs(prefix));
-                        self.data =
+                        *self.data_mut() =
                             MptNodeData::Leaf(to_encoded_path(&self_nibs, true), mem::take(value));
                     }
                     // for an extension, replace the extension with the extended extension
                     MptNodeData::Extension(prefix, node) => {
                         self_nibs.extend(prefix_nibs(prefix));
-                        self.data = MptNodeData::Extension(
+                        *self.data_mut() = MptNodeData::Extension(
                             to_encoded_path(&self_nibs, false),
                             mem::take(node),
                         );
@@ -656,9 +812,9 @@ impl MptNode {
     }
 
     fn insert_internal(&mut self, key_nibs: &[u8], value: Vec<u8>) -> Result<bool, Error> {
-        match &mut self.data {
+        match self.data_mut() {
             MptNodeData::Null => {
-                self.data = MptNodeData::Leaf(to_encoded_path(key_nibs, true), value);
+                *self.data_mut() = MptNodeData::Leaf(to_encoded_path(key_nibs, true), value);
             }
             MptNodeData::Branch(children) => {
                 if let Some((i, tail)) = key_nibs.split_first() {
@@ -711,12 +867,12 @@ impl MptNode {
                     let branch = MptNodeData::Branch(children);
                     if common_len > 0 {
                         // create parent extension for new branch
-                        self.data = MptNodeData::Extension(
+                        *self.data_mut() = MptNodeData::Extension(
                             to_encoded_path(&self_nibs[..common_len], false),
                             Box::new(branch.into()),
                         );
                     } else {
-                        self.data = branch;
+                        *self.data_mut() = branch;
                     }
                 }
             }
@@ -754,12 +910,12 @@ impl MptNode {
                     let branch = MptNodeData::Branch(children);
                     if common_len > 0 {
                         // Create parent extension for new branch
-                        self.data = MptNodeData::Extension(
+                        *self.data_mut() = MptNodeData::Extension(
                             to_encoded_path(&self_nibs[..common_len], false),
                             Box::new(branch.into()),
                         );
                     } else {
-                        self.data = branch;
+                        *self.data_mut() = branch;
                     }
                 }
             }
@@ -827,7 +983,7 @@ impl MptNode {
 
     /// Returns the length of the RLP payload of the node.
     fn payload_length(&self) -> usize {
-        match &self.data {
+        match self.as_data() {
             MptNodeData::Null => 0,
             MptNodeData::Branch(nodes) => {
                 1 + nodes
@@ -993,6 +1149,93 @@ pub fn resolve_nodes(root: &MptNode, node_store: &HashMap<MptNodeReference, MptN
     trie
 }
 
+/// RLP-encoded trie nodes indexed by their hash, from which tries are lazily decoded.
+#[derive(Debug, Default)]
+pub struct NodeStore {
+    nodes: HashMap<B256, Bytes>,
+    /// The hashes of the nodes decoded so far, if they are recorded.
+    decoded: Option<Mutex<HashSet<B256>>>,
+}
+
+impl NodeStore {
+    /// Creates a store of the given nodes.
+    ///
+    /// Every node is decoded once to be validated, so that a trie referencing an undecodable node
+    /// by hash is rejected here rather than failing when the node is first accessed.
+    pub fn new(nodes: impl IntoIterator<Item = Bytes>) -> Result<Self, FromProofError> {
+        let nodes = nodes
+            .into_iter()
+            .map(|node| {
+                let hash = keccak256(&node);
+                MptNode::decode(&node)
+                    .map_err(|err| FromProofError::InvalidWitnessNode(hash, err))?;
+
+                Ok((hash, node))
+            })
+            .collect::<Result<_, FromProofError>>()?;
+
+        Ok(Self { nodes, decoded: None })
+    }
+
+    /// Creates a store of the given nodes, which records the hashes of the nodes decoded from it.
+    pub fn recording(nodes: impl IntoIterator<Item = Bytes>) -> Result<Self, FromProofError> {
+        Ok(Self { decoded: Some(Mutex::default()), ..Self::new(nodes)? })
+    }
+
+    /// Returns the hashes of the nodes decoded so far, or `None` if the store does not record
+    /// them.
+    pub fn decoded(&self) -> Option<HashSet<B256>> {
+        self.decoded.as_ref().map(|decoded| decoded.lock().unwrap().clone())
+    }
+}
+
+/// Creates the trie with the given root hash from the nodes of `store`.
+///
+/// The nodes are lazily decoded on first access, and the nodes missing from `store` are kept as
+/// digests. As every node is looked up by the hash of its encoding, the root hash of the returned
+/// trie is `root` by construction.
+pub fn resolve_nodes_by_hash(root: B256, store: &Arc<NodeStore>) -> MptNode {
+    if root == EMPTY_ROOT {
+        return MptNode::default();
+    }
+
+    lazy_node(root, store)
+}
+
+/// Returns the lazily decoded node with the given hash, or a digest if it is not in `store`.
+fn lazy_node(digest: B256, store: &Arc<NodeStore>) -> MptNode {
+    let Some(rlp) = store.nodes.get(&digest) else {
+        return MptNodeData::Digest(digest).into();
+    };
+
+    // memoize the hash, since it's already known
+    let cached_reference = (rlp.len() >= 32).then_some(MptNodeReference::Digest(digest));
+
+    MptNode {
+        data: OnceLock::new(),
+        encoded: Some(EncodedNode { digest, rlp: rlp.clone(), store: store.clone() }),
+        cached_reference: Mutex::new(cached_reference),
+    }
+}
+
+/// Replaces the digests in the node data and its inline children by lazily decoded nodes.
+fn resolve_lazily(data: &mut MptNodeData, store: &Arc<NodeStore>) {
+    let children: Vec<&mut MptNode> = match data {
+        MptNodeData::Null | MptNodeData::Leaf(_, _) | MptNodeData::Digest(_) => return,
+        MptNodeData::Branch(children) => {
+            children.iter_mut().flatten().map(|child| &mut **child).collect()
+        }
+        MptNodeData::Extension(_, child) => vec![&mut **child],
+    };
+
+    for child in children {
+        match child.data_mut() {
+            MptNodeData::Digest(digest) => *child = lazy_node(*digest, store),
+            data => resolve_lazily(data, store),
+        }
+    }
+}
+
 /// Returns a list of all possible nodes that can be created by shortening the path of the
 /// given node.
 /// When nodes in an MPT are deleted, leaves or extensions may be extended. To still be
@@ -1212,7 +1455,11 @@ fn node_from_digest(digest: B256) -> MptNode {
 
 #[cfg(test)]
 mod tests {
+    use std::collections::BTreeMap;
+
     use hex_literal::hex;
+    use proptest::{collection::vec, option, prelude::*};
+    use reth_trie::HashBuilder;
 
     use super::*;
 
@@ -1332,7 +1579,7 @@ mod tests {
         let exp_hash = trie.hash();
 
         // replace one node with its digest
-        let MptNodeData::Extension(_, node) = &mut trie.data else { panic!("extension expected") };
+        let MptNodeData::Extension(_, node) = trie.data_mut() else { panic!("extension expected") };
         **node = MptNodeData::Digest(node.hash()).into();
         assert!(node.is_digest());
 
@@ -1344,6 +1591,54 @@ mod tests {
         trie.get(b"a0").unwrap_err();
     }
 
+    #[test]
+    pub fn test_prove() {
+        let mut trie = MptNode::default();
+        for i in 0..256u64 {
+            trie.insert_rlp(&keccak(i.to_be_bytes()), i).unwrap();
+        }
+
+        // proofs of inclusion
+        for i in 0..256u64 {
+            let key = keccak(i.to_be_bytes());
+            let proof_trie =
+                mpt_from_proof(&parse_proof(&trie.prove(&key).unwrap()).unwrap()).unwrap();
+            assert_eq!(proof_trie.hash(), trie.hash());
+            assert_eq!(proof_trie.get_rlp::<u64>(&key).unwrap(), Some(i));
+        }
+
+        // proofs of exclusion
+        for i in 256..512u64 {
+            let key = keccak(i.to_be_bytes());
+            let proof_nodes = parse_proof(&trie.prove(&key).unwrap()).unwrap();
+            assert_eq!(mpt_from_proof(&proof_nodes).unwrap().hash(), trie.hash());
+            assert!(is_not_included(&key, &proof_nodes).unwrap());
+        }
+
+        // the nodes of a tiny trie are all inlined in the root
+        let mut tiny_trie = MptNode::default();
+        tiny_trie.insert_rlp(b"aa", 0u8).unwrap();
+        tiny_trie.insert_rlp(b"ab", 1u8).unwrap();
+        let proof = tiny_trie.prove(b"ab").unwrap();
+        assert_eq!(proof, vec![Bytes::from(tiny_trie.to_rlp())]);
+        let proof_trie = mpt_from_proof(&parse_proof(&proof).unwrap()).unwrap();
+        assert_eq!(proof_trie.get_rlp::<u8>(b"ab").unwrap(), Some(1));
+
+        // the proofs of an empty trie are empty
+        assert!(MptNode::default().prove(&hex!("00")).unwrap().is_empty());
+
+        // the unresolved nodes cannot be proven
+        let digest: MptNode = MptNodeData::Digest(trie.hash()).into();
+        assert!(matches!(digest.prove(&hex!("00")), Err(Error::NodeNotResolved(_))));
+    }
+
+    #[test]
+    pub fn test_decode_empty_path() {
+        // a leaf or extension must at least have the prefix of its path
+        assert!(MptNode::decode(hex!("c28080")).is_err());
+        assert!(MptNode::decode(hex!("c28001")).is_err());
+    }
+
     #[test]
     pub fn test_for_each_leaves() {
         let mut trie = MptNode::default();
@@ -1477,4 +1772,79 @@ mod tests {
         }
         assert!(trie.is_empty());
     }
+
+    #[test]
+    pub fn test_shorten_node_path() {
+        let leaf: MptNode = MptNodeData::Leaf(to_encoded_path(&[1, 2, 3], true), vec![0x2a]).into();
+        let shortened = shorten_node_path(&leaf);
+        assert_eq!(shortened.len(), 4);
+        for (i, node) in shortened.iter().enumerate() {
+            assert_eq!(node.nibs(), vec![1, 2, 3][i..].to_vec());
+            assert_eq!(node.get(&[]).unwrap().is_some(), i == 3);
+        }
+
+        let child = Box::new(leaf.clone());
+        let extension: MptNode =
+            MptNodeData::Extension(to_encoded_path(&[4, 5], false), child.clone()).into();
+        let shortened = shorten_node_path(&extension);
+        assert_eq!(shortened.len(), 3);
+        // the extension with an empty path is kept, even if it is not a valid node
+        assert_eq!(shortened[2], MptNodeData::Extension(to_encoded_path(&[], false), child).into());
+
+        // the nodes without a path cannot be shortened
+        let mut branch = MptNode::default();
+        branch.insert_rlp(&[0x10], 1u8).unwrap();
+        branch.insert_rlp(&[0x20], 2u8).unwrap();
+        assert!(matches!(branch.as_data(), MptNodeData::Branch(_)));
+        assert!(shorten_node_path(&branch).is_empty());
+        assert!(shorten_node_path(&MptNode::default()).is_empty());
+        assert!(shorten_node_path(&MptNodeData::Digest(B256::ZERO).into()).is_empty());
+    }
+
+    proptest! {
+        #[test]
+        fn test_random_ops_against_hash_builder(
+            ops in vec((0..64u64, option::of(any::<u64>())), 0..256),
+            proof_key in 0..128u64,
+        ) {
+            let mut trie = MptNode::default();
+            let mut expected = BTreeMap::new();
+            // use a small key space, so that keys are updated and deleted
+            for (key, value) in ops {
+                let key = B256::from(keccak(key.to_be_bytes()));
+                match value {
+                    Some(value) => {
+                        trie.insert_rlp(key.as_slice(), value).unwrap();
+                        expected.insert(key, value);
+                    }
+                    None => {
+                        prop_assert_eq!(
+                            trie.delete(key.as_slice()).unwrap(),
+                            expected.remove(&key).is_some()
+                        );
+                    }
+                }
+            }
+
+            let mut hash_builder = HashBuilder::default();
+            for (key, value) in &expected {
+                hash_builder.add_leaf(Nibbles::unpack(key), &alloy_rlp::encode(value));
+            }
+            prop_assert_eq!(trie.hash(), hash_builder.root());
+
+            for (key, value) in &expected {
+                prop_assert_eq!(trie.get_rlp::<u64>(key.as_slice()).unwrap(), Some(*value));
+            }
+
+            // the proof of any key, included or not, resolves the same trie
+            let proof_key = keccak(proof_key.to_be_bytes());
+            let proof_trie =
+                mpt_from_proof(&parse_proof(&trie.prove(&proof_key).unwrap()).unwrap()).unwrap();
+            prop_assert_eq!(proof_trie.hash(), trie.hash());
+            prop_assert_eq!(
+                proof_trie.get_rlp::<u64>(&proof_key).unwrap(),
+                expected.get(&B256::from(proof_key)).copied()
+            );
+        }
+    }
 }
@@ -21,6 +21,9 @@ pub enum RpcDbError {
     BlockNotFound(u64),
     #[error("failed to find trie node preimage")]
     PreimageNotFound,
+    #[cfg(feature = "execution-witness")]
+    #[error("failed to decode a witness header: {0}")]
+    InvalidWitnessHeader(alloy_rlp::Error),
     #[error("poisoned lock")]
     Poisoned,
 }
@@ -33,7 +33,7 @@ impl<P: Provider<N> + Clone, N: Network> ExecutionWitnessRpcDb<P, N> {
     pub async fn new(provider: P, block_number: u64, state_root: B256) -> Result<Self, RpcDbError> {
         let execution_witness = provider.debug_execution_witness((block_number + 1).into()).await?;
 
-        let state = EthereumState::from_execution_witness(&execution_witness, state_root);
+        let state = EthereumState::from_execution_witness(&execution_witness, state_root)?;
 
         let codes = execution_witness
             .codes
@@ -44,9 +44,9 @@ impl<P: Provider<N> + Clone, N: Network> ExecutionWitnessRpcDb<P, N> {
         let ancestor_headers = execution_witness
             .headers
             .iter()
-            .map(|encoded| Header::decode(&mut encoded.as_ref()).unwrap())
-            .map(|h| (h.number, h))
-            .collect();
+            .map(|encoded| Header::decode(&mut encoded.as_ref()).map(|h| (h.number, h)))
+            .collect::<Result<_, _>>()
+            .map_err(RpcDbError::InvalidWitnessHeader)?;
 
         let db = Self { provider, state, codes, ancestor_headers, phantom: PhantomData };
 
@@ -0,0 +1,118 @@
+//! Mutates a serialized client input built from an honest state, and executes it as the guest
+//! does.
+//!
+//! The mutated input must either be rejected with a `ClientError`, or be consistent with the
+//! honest execution: a block on top of the honest parent must result in the honest state root,
+//! and the unchanged block in the honest header.
+
+#![no_main]
+
+use std::sync::Arc;
+
+use alloy_consensus::{Block, BlockBody, Header};
+use alloy_primitives::{keccak256, Address, B256, U256};
+use arbitrary::Arbitrary;
+use libfuzzer_sys::fuzz_target;
+use reth_chainspec::ChainSpecBuilder;
+use reth_primitives_traits::Account;
+use reth_trie::{HashedPostState, HashedStorage, EMPTY_ROOT_HASH};
+use rsp_client_executor::{executor::EthClientExecutor, io::EthClientExecutorInput};
+use rsp_mpt::FlatWitness;
+use rsp_primitives::genesis::Genesis;
+
+#[derive(Debug, Arbitrary)]
+struct Input {
+    /// The honest accounts, with their nonce and storage slots.
+    accounts: Vec<(u8, u64, Vec<(u8, u64)>)>,
+    /// The mutations of the serialized input, as positions and bytes to XOR.
+    mutations: Vec<(u32, u8)>,
+}
+
+fn address(i: u8) -> Address {
+    Address::repeat_byte(i)
+}
+
+fn slot(i: u8) -> B256 {
+    B256::from(U256::from(i))
+}
+
+fuzz_target!(|input: Input| {
+    let mut post_state = HashedPostState::default();
+    for (i, nonce, storage) in &input.accounts {
+        let hashed_address = keccak256(address(*i));
+        let account = Account { nonce: *nonce, balance: U256::ZERO, bytecode_hash: None };
+        post_state.accounts.insert(hashed_address, Some(account));
+        post_state.storages.insert(
+            hashed_address,
+            HashedStorage::from_iter(
+                false,
+                storage.iter().map(|(j, value)| (keccak256(slot(*j)), U256::from(*value))),
+            ),
+        );
+    }
+
+    let mut honest_state =
+        FlatWitness { state_root: EMPTY_ROOT_HASH, storage_roots: vec![], nodes: vec![] }
+            .into_state()
+            .unwrap();
+    honest_state.update(&post_state).unwrap();
+
+    // post-merge, so that an empty block leaves the state unchanged
+    let chain_spec = Arc::new(ChainSpecBuilder::mainnet().paris_activated().build());
+    let executor = EthClientExecutor::eth(chain_spec, None);
+
+    let parent_header = Header {
+        state_root: honest_state.state_root(),
+        gas_limit: 30_000_000,
+        base_fee_per_gas: Some(7),
+        ..Default::default()
+    };
+    let honest_parent_hash = parent_header.hash_slow();
+    let honest = EthClientExecutorInput {
+        current_block: Block {
+            header: Header {
+                number: 1,
+                parent_hash: honest_parent_hash,
+                state_root: honest_state.state_root(),
+                gas_limit: 30_000_000,
+                base_fee_per_gas: Some(7),
+                timestamp: 1,
+                ..Default::default()
+            },
+            body: BlockBody::default(),
+        },
+        ancestor_headers: vec![parent_header],
+        parent_state: honest_state,
+        bytecodes: vec![],
+        genesis: Genesis::Mainnet,
+        custom_beneficiary: None,
+        opcode_tracking: false,
+    };
+
+    let mut bytes = bincode::serialize(&honest).unwrap();
+    let honest_header = executor.execute(honest.clone()).expect("the honest input is valid");
+
+    for (position, mask) in input.mutations {
+        let position = position as usize % bytes.len();
+        bytes[position] ^= mask;
+    }
+
+    let Ok(input) = bincode::deserialize::<EthClientExecutorInput>(&bytes) else {
+        return;
+    };
+    let same_block = input.current_block == honest.current_block;
+
+    let Ok(header) = executor.execute(input) else {
+        return;
+    };
+
+    // a different parent is committed in the header, so it is not a forgery
+    if header.parent_hash != honest_parent_hash {
+        return;
+    }
+
+    assert_eq!(header.state_root, honest_header.state_root);
+    if same_block {
+        assert_eq!(header, honest_header);
+    }
+});
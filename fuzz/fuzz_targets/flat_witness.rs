@@ -0,0 +1,41 @@
+//! Rebuilds a state from a flat witness of arbitrary nodes, referenced by hash as the roots of
+//! the tries, and reads from it.
+//!
+//! The undecodable nodes must be rejected when the state is rebuilt, and reading from the
+//! accepted state must never panic.
+
+#![no_main]
+
+use alloy_primitives::{keccak256, Bytes};
+use libfuzzer_sys::fuzz_target;
+use rsp_mpt::FlatWitness;
+
+fuzz_target!(|input: (Vec<Vec<u8>>, Vec<[u8; 32]>)| {
+    let (nodes, keys) = input;
+    let nodes: Vec<Bytes> = nodes.into_iter().map(Bytes::from).collect();
+    let Some(state_root) = nodes.first().map(keccak256) else {
+        return;
+    };
+
+    // every node is the root of a storage trie, so that all of them are reachable
+    let storage_roots = nodes
+        .iter()
+        .map(|node| {
+            let hash = keccak256(node);
+            (hash, hash)
+        })
+        .collect();
+
+    let witness = FlatWitness { state_root, storage_roots, nodes };
+    let Ok(state) = witness.into_state() else {
+        return;
+    };
+
+    for key in &keys {
+        let _ = state.state_trie.get(key);
+        for storage_trie in state.storage_tries.values() {
+            let _ = storage_trie.get(key);
+        }
+    }
+    let _ = state.state_root();
+});
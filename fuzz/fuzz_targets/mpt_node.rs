@@ -0,0 +1,28 @@
+//! Decodes arbitrary bytes as an MPT node, and operates on the decoded node.
+//!
+//! Decoding must never panic, and the decoded nodes must behave as tries.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use rsp_mpt::MptNode;
+
+fuzz_target!(|input: (Vec<u8>, [u8; 32], Vec<u8>)| {
+    let (encoded, key, value) = input;
+    let Ok(mut node) = MptNode::decode(&encoded) else {
+        return;
+    };
+
+    // the node can be re-encoded, and the re-encoded node has the same hash
+    let reencoded = MptNode::decode(alloy_rlp::encode(&node)).expect("re-encoded node is valid");
+    assert_eq!(reencoded.hash(), node.hash());
+
+    let _ = node.get(&key);
+    let _ = node.prove(&key);
+
+    if !value.is_empty() && node.insert(&key, value.clone()).is_ok() {
+        assert_eq!(node.get(&key).unwrap(), Some(value.as_slice()));
+        assert!(node.delete(&key).unwrap());
+        assert_eq!(node.get(&key).unwrap(), None);
+    }
+});